@@ -2,81 +2,416 @@ use crate::github::{GitTree, Repository};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs::File;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
 use std::io::{BufReader, Read, Write};
+use std::path::Path;
 use tracing::info;
 
-const CACHE_FILE: &str = ".data_cache.bin.gz";
+pub const CACHE_FILE: &str = ".data_cache.bin.gz";
+/// File contents keyed by git blob SHA. Kept apart from `CACHE_FILE` since
+/// it is much larger and only grows when repositories change.
+pub const BLOB_CACHE_FILE: &str = ".blob_cache.bin.gz";
+
+/// Written before the postcard payload of both cache files.
+const CACHE_MAGIC: &[u8; 4] = b"AHDC";
+/// Bump whenever a cached type changes shape. postcard is not
+/// self-describing, so an older payload can't be read into the new types;
+/// caches of another version are dropped instead.
+pub const CACHE_VERSION: u32 = 4;
+
+/// Entries not used for this long are dropped at the end of `update`.
+pub const DEFAULT_MAX_AGE_DAYS: u64 = 30;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheEntry<T> {
     pub data: T,
     pub etag: Option<String>,
+    /// `Last-Modified` of the response, sent back as `If-Modified-Since`
+    /// when there is no ETag.
+    pub last_modified: Option<String>,
+    /// Unix seconds of the last run that read or wrote the entry.
+    pub last_access: u64,
+}
+
+impl<T> CacheEntry<T> {
+    pub fn new(data: T, etag: Option<String>) -> Self {
+        Self {
+            data,
+            etag,
+            last_modified: None,
+            last_access: now(),
+        }
+    }
+
+    pub fn touch(&mut self) {
+        self.last_access = now();
+    }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// Version of what the builder makes of a repository. Bump it whenever the
+/// builder's output changes, so existing plugins are rebuilt rather than
+/// only having their counters refreshed.
+pub const BUILDER_VERSION: u32 = 1;
+
+/// What a plugin was last built from. When neither the default-branch tree,
+/// the set of releases, the repository metadata nor the builder changed,
+/// only its counters need refreshing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RepoRevision {
+    pub tree_sha: String,
+    /// Sorted ids of the repository's releases.
+    pub release_ids: Vec<u64>,
+    /// Digest of the repository's description, topics, license and owner
+    /// avatar, which the plugin takes fields from.
+    pub metadata_hash: u64,
+    /// `BUILDER_VERSION` the plugin was built with.
+    pub builder_version: u32,
+}
+
+/// Body of a conditional GET, with the `Link` header that points at the
+/// next page of a paginated listing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub body: String,
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DataCache {
+    /// Keyed by `owner/repo`.
     pub repositories: HashMap<String, CacheEntry<Repository>>,
+    /// Keyed by `owner/repo/branch`.
     pub trees: HashMap<String, CacheEntry<GitTree>>,
+    /// Last indexed revision, keyed by plugin id.
+    pub revisions: HashMap<String, CacheEntry<RepoRevision>>,
+    /// Raw bodies of other conditional GETs (releases, contributors,
+    /// READMEs, searches), keyed by URL.
+    pub responses: HashMap<String, CacheEntry<CachedResponse>>,
+    /// Stored in `BLOB_CACHE_FILE`.
+    #[serde(skip)]
+    pub blobs: HashMap<String, CacheEntry<String>>,
+}
+
+/// Which entries `DataCache::prune` keeps.
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    /// Drop entries not accessed for this many days.
+    pub max_age_days: Option<u64>,
+    /// Lowercase `owner/repo` names and plugin ids still in the index. When
+    /// set, entries of other repositories and plugins are dropped.
+    pub referenced: Option<HashSet<String>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CacheCounts {
+    pub repositories: usize,
+    pub trees: usize,
+    pub revisions: usize,
+    pub responses: usize,
+    pub blobs: usize,
+}
+
+impl CacheCounts {
+    pub fn total(&self) -> usize {
+        self.repositories + self.trees + self.revisions + self.responses + self.blobs
+    }
 }
 
 impl DataCache {
     pub fn load() -> Self {
-        let file = match File::open(CACHE_FILE) {
-            Ok(f) => f,
-            Err(_) => return Self::default(),
-        };
-
-        let mut decoder = GzDecoder::new(BufReader::new(file));
-        let mut bytes = Vec::new();
-        if decoder.read_to_end(&mut bytes).is_err() {
-            return Self::default();
+        let mut cache: DataCache = read_gz(CACHE_FILE).unwrap_or_default();
+        let count = cache.counts().total();
+        if count > 0 {
+            info!(entries = count, "Loaded data cache");
         }
 
-        match postcard::from_bytes(&bytes) {
-            Ok(cache) => {
-                let cache: DataCache = cache;
-                let count = cache.repositories.len() + cache.trees.len();
-                if count > 0 {
-                    info!(entries = count, "Loaded data cache");
-                }
-                cache
-            }
-            Err(e) => {
-                info!(error = %e, "Failed to load cache, starting fresh");
-                Self::default()
-            }
+        cache.blobs = read_gz(BLOB_CACHE_FILE).unwrap_or_default();
+        if !cache.blobs.is_empty() {
+            info!(blobs = cache.blobs.len(), "Loaded blob cache");
         }
+        cache
     }
 
+    /// Write both cache files. An empty section removes its file, so a
+    /// pruned-away or invalidated cache doesn't linger on disk.
     pub fn save(&self) {
-        let count = self.repositories.len() + self.trees.len();
+        let count = self.counts().total() - self.blobs.len();
         if count == 0 {
-            return;
+            let _ = fs::remove_file(CACHE_FILE);
+        } else if write_gz(CACHE_FILE, self) {
+            info!(entries = count, "Saved data cache");
         }
 
-        let bytes = match postcard::to_allocvec(self) {
-            Ok(b) => b,
-            Err(e) => {
-                info!(error = %e, "Failed to serialize cache");
-                return;
-            }
-        };
+        if self.blobs.is_empty() {
+            let _ = fs::remove_file(BLOB_CACHE_FILE);
+        } else if write_gz(BLOB_CACHE_FILE, &self.blobs) {
+            info!(blobs = self.blobs.len(), "Saved blob cache");
+        }
+    }
+
+    /// Remove both cache files.
+    pub fn clear() {
+        let _ = fs::remove_file(CACHE_FILE);
+        let _ = fs::remove_file(BLOB_CACHE_FILE);
+    }
 
-        let file = match File::create(CACHE_FILE) {
-            Ok(f) => f,
-            Err(e) => {
-                info!(error = %e, "Failed to create cache file");
-                return;
-            }
+    pub fn counts(&self) -> CacheCounts {
+        CacheCounts {
+            repositories: self.repositories.len(),
+            trees: self.trees.len(),
+            revisions: self.revisions.len(),
+            responses: self.responses.len(),
+            blobs: self.blobs.len(),
+        }
+    }
+
+    /// Drop stale and unreferenced entries, returning how many went.
+    ///
+    /// Blobs can't be traced back to a repository, so they are kept only
+    /// while one of the remaining trees still contains them.
+    pub fn prune(&mut self, options: &PruneOptions) -> CacheCounts {
+        let before = self.counts();
+        let cutoff = options
+            .max_age_days
+            .map(|days| now().saturating_sub(days * 24 * 60 * 60));
+        let fresh = |last_access: u64| cutoff.is_none_or(|c| last_access >= c);
+        let referenced = |key: &str| {
+            options
+                .referenced
+                .as_ref()
+                .is_none_or(|r| r.contains(&key.to_lowercase()))
         };
 
-        let mut encoder = GzEncoder::new(file, Compression::default());
-        match encoder.write_all(&bytes) {
-            Ok(_) => info!(entries = count, "Saved data cache"),
-            Err(e) => info!(error = %e, "Failed to write cache"),
+        self.repositories
+            .retain(|key, e| fresh(e.last_access) && referenced(key));
+        self.trees.retain(|key, e| {
+            let repo = key.splitn(3, '/').take(2).collect::<Vec<_>>().join("/");
+            fresh(e.last_access) && referenced(&repo)
+        });
+        self.revisions
+            .retain(|id, e| fresh(e.last_access) && referenced(id));
+        self.responses.retain(|url, e| {
+            fresh(e.last_access) && repo_of_url(url).is_none_or(|repo| referenced(&repo))
+        });
+
+        let live_blobs: HashSet<&str> = self
+            .trees
+            .values()
+            .flat_map(|t| t.data.tree.iter().map(|e| e.sha.as_str()))
+            .collect();
+        self.blobs
+            .retain(|sha, e| fresh(e.last_access) && live_blobs.contains(sha.as_str()));
+
+        let after = self.counts();
+        CacheCounts {
+            repositories: before.repositories - after.repositories,
+            trees: before.trees - after.trees,
+            revisions: before.revisions - after.revisions,
+            responses: before.responses - after.responses,
+            blobs: before.blobs - after.blobs,
         }
     }
 }
+
+/// `owner/repo` of a `/repos/{owner}/{repo}/...` API URL. Other URLs, such
+/// as searches, belong to no repository.
+fn repo_of_url(url: &str) -> Option<String> {
+    let rest = url.split_once("/repos/")?.1;
+    let mut parts = rest.split(['/', '?']);
+    Some(format!("{}/{}", parts.next()?, parts.next()?))
+}
+
+/// Size in bytes of a cache file, or 0 when it doesn't exist.
+pub fn file_size(path: &str) -> u64 {
+    fs::metadata(Path::new(path)).map(|m| m.len()).unwrap_or(0)
+}
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+fn read_gz<T: DeserializeOwned>(path: &str) -> Option<T> {
+    let file = File::open(path).ok()?;
+
+    let mut decoder = GzDecoder::new(BufReader::new(file));
+    let mut bytes = Vec::new();
+    decoder.read_to_end(&mut bytes).ok()?;
+
+    let payload = match decode_header(&bytes) {
+        Ok(payload) => payload,
+        Err(e) => {
+            info!(path = %path, reason = %e, "Cache invalidated, starting fresh");
+            return None;
+        }
+    };
+
+    match postcard::from_bytes(payload) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            info!(path = %path, error = %e, "Failed to load cache, starting fresh");
+            None
+        }
+    }
+}
+
+fn write_gz<T: Serialize>(path: &str, data: &T) -> bool {
+    let mut bytes = encode_header();
+    match postcard::to_extend(data, bytes) {
+        Ok(b) => bytes = b,
+        Err(e) => {
+            info!(path = %path, error = %e, "Failed to serialize cache");
+            return false;
+        }
+    }
+
+    let file = match File::create(path) {
+        Ok(f) => f,
+        Err(e) => {
+            info!(path = %path, error = %e, "Failed to create cache file");
+            return false;
+        }
+    };
+
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    match encoder.write_all(&bytes).and_then(|_| encoder.finish().map(|_| ())) {
+        Ok(()) => true,
+        Err(e) => {
+            info!(path = %path, error = %e, "Failed to write cache");
+            false
+        }
+    }
+}
+
+fn encode_header() -> Vec<u8> {
+    let mut bytes = CACHE_MAGIC.to_vec();
+    bytes.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+    bytes
+}
+
+fn decode_header(bytes: &[u8]) -> Result<&[u8], String> {
+    let Some(rest) = bytes.strip_prefix(CACHE_MAGIC.as_slice()) else {
+        return Err("unversioned cache".to_string());
+    };
+    let Some((version, payload)) = rest.split_first_chunk::<4>() else {
+        return Err("truncated header".to_string());
+    };
+    let version = u32::from_le_bytes(*version);
+    if version != CACHE_VERSION {
+        return Err(format!(
+            "version {} does not match {}",
+            version, CACHE_VERSION
+        ));
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::{GitTreeEntry, Owner};
+
+    fn repository(full_name: &str) -> Repository {
+        Repository {
+            id: 1,
+            full_name: full_name.to_string(),
+            name: full_name.rsplit('/').next().unwrap().to_string(),
+            description: None,
+            html_url: String::new(),
+            stargazers_count: 0,
+            forks_count: 0,
+            created_at: String::new(),
+            updated_at: String::new(),
+            pushed_at: String::new(),
+            owner: Owner {
+                login: "example".to_string(),
+                avatar_url: String::new(),
+                html_url: String::new(),
+            },
+            license: None,
+            topics: Vec::new(),
+            is_template: false,
+            fork: false,
+            archived: false,
+            default_branch: None,
+            contributors_url: String::new(),
+        }
+    }
+
+    fn tree(sha: &str) -> GitTree {
+        GitTree {
+            sha: "t".to_string(),
+            tree: vec![GitTreeEntry {
+                path: "build.gradle.kts".to_string(),
+                entry_type: "blob".to_string(),
+                sha: sha.to_string(),
+                size: None,
+            }],
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let mut bytes = encode_header();
+        bytes.push(7);
+        assert_eq!(decode_header(&bytes).unwrap(), &[7]);
+
+        assert!(decode_header(&[1, 2, 3]).is_err());
+        let mut old = CACHE_MAGIC.to_vec();
+        old.extend_from_slice(&(CACHE_VERSION + 1).to_le_bytes());
+        assert!(decode_header(&old).is_err());
+    }
+
+    #[test]
+    fn test_prune_by_age_and_reference() {
+        let mut cache = DataCache::default();
+        cache.repositories.insert(
+            "Example/Kept".to_string(),
+            CacheEntry::new(repository("Example/Kept"), None),
+        );
+        cache.repositories.insert(
+            "example/gone".to_string(),
+            CacheEntry::new(repository("example/gone"), None),
+        );
+        let mut stale = CacheEntry::new(repository("example/old"), None);
+        stale.last_access = 0;
+        cache.repositories.insert("example/old".to_string(), stale);
+        cache.trees.insert(
+            "Example/Kept/main".to_string(),
+            CacheEntry::new(tree("b1"), None),
+        );
+        cache.trees.insert(
+            "example/gone/main".to_string(),
+            CacheEntry::new(tree("b2"), None),
+        );
+        cache
+            .blobs
+            .insert("b1".to_string(), CacheEntry::new(String::new(), None));
+        cache.responses.insert(
+            "https://api.github.com/repos/example/gone/releases?per_page=30".to_string(),
+            CacheEntry::new(CachedResponse::default(), None),
+        );
+        cache.responses.insert(
+            "https://api.github.com/search/code?q=allay".to_string(),
+            CacheEntry::new(CachedResponse::default(), None),
+        );
+        cache
+            .blobs
+            .insert("b2".to_string(), CacheEntry::new(String::new(), None));
+
+        let removed = cache.prune(&PruneOptions {
+            max_age_days: Some(30),
+            referenced: Some(["example/kept", "example/old"].map(String::from).into()),
+        });
+
+        assert_eq!(removed.repositories, 2);
+        assert_eq!(removed.trees, 1);
+        assert_eq!(removed.blobs, 1);
+        assert_eq!(removed.responses, 1);
+        assert!(cache.repositories.contains_key("Example/Kept"));
+        assert!(cache.blobs.contains_key("b1"));
+    }
+}
@@ -1,18 +1,40 @@
 use super::auth::GitHubAppAuth;
+use super::error::GitHubError;
+use super::graphql::{RepositorySnapshot, parse_repositories_response, repositories_query};
+use super::pool::TokenBucket;
+use super::transport::{Transport, TransportResponse, UreqTransport};
 use super::types::*;
-use crate::cache::{CacheEntry, DataCache};
+use crate::cache::{CacheEntry, CachedResponse, DataCache, RepoRevision};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
-use tracing::{debug, debug_span, info_span, warn};
+use tracing::{debug, debug_span, info, info_span, warn};
 
-const MAX_CONCURRENT: usize = 10;
+/// Workers used by `execute_parallel` unless `--concurrency` says otherwise.
+pub const DEFAULT_CONCURRENCY: usize = 10;
+/// Items started per second once the initial burst of one per worker is
+/// spent.
+const ITEMS_PER_SEC: f64 = 6.0;
+/// Repositories per GraphQL query; keeps each response well under GitHub's
+/// node limit even with 30 releases of 20 assets each.
+const GRAPHQL_BATCH_SIZE: usize = 25;
 
 const API_BASE: &str = "https://api.github.com";
+const GRAPHQL_URL: &str = "https://api.github.com/graphql";
+const ACCEPT_JSON: &str = "application/vnd.github+json";
+const ACCEPT_RAW: &str = "application/vnd.github.raw+json";
 const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(3600 - 300);
 const RATE_LIMIT_BUFFER: usize = 5;
+/// Pages fetched per paginated listing unless `set_max_pages` says otherwise.
+pub const DEFAULT_MAX_PAGES: usize = 10;
+/// Sub-tree requests spent on a repository whose recursive tree came back
+/// truncated.
+const MAX_TREE_WALK: usize = 200;
+/// Upper bound on a single wait for the quota reset, in case the header is
+/// off.
+const MAX_RESET_WAIT: u64 = 3600;
 
 const USER_AGENT: &str = concat!(
     "AllayIndexer/",
@@ -20,27 +42,6 @@ const USER_AGENT: &str = concat!(
     " (+https://github.com/AllayMC/AllayHub)"
 );
 
-struct ResponseCache {
-    repositories: HashMap<String, CacheEntry<Repository>>,
-    trees: HashMap<String, CacheEntry<GitTree>>,
-}
-
-impl ResponseCache {
-    fn from_data_cache(cache: DataCache) -> Self {
-        Self {
-            repositories: cache.repositories,
-            trees: cache.trees,
-        }
-    }
-
-    fn to_data_cache(&self) -> DataCache {
-        DataCache {
-            repositories: self.repositories.clone(),
-            trees: self.trees.clone(),
-        }
-    }
-}
-
 #[derive(Clone)]
 pub enum AuthMethod {
     Token(String),
@@ -71,28 +72,41 @@ impl RateLimit {
         self.limit.load(Ordering::SeqCst)
     }
 
+    /// Unix seconds at which the quota is refilled.
+    pub fn reset(&self) -> usize {
+        self.reset.load(Ordering::SeqCst)
+    }
+
     pub fn has_remaining(&self) -> bool {
         self.remaining.load(Ordering::SeqCst) > RATE_LIMIT_BUFFER
     }
-}
 
-impl Clone for RateLimit {
-    fn clone(&self) -> Self {
-        Self {
-            remaining: AtomicUsize::new(self.remaining.load(Ordering::SeqCst)),
-            limit: AtomicUsize::new(self.limit.load(Ordering::SeqCst)),
-            reset: AtomicUsize::new(self.reset.load(Ordering::SeqCst)),
-        }
+    /// Seconds to sleep until the quota is refilled, with a second of slack
+    /// for clock skew.
+    fn secs_until_reset(&self, now: u64) -> u64 {
+        (self.reset() as u64).saturating_sub(now).min(MAX_RESET_WAIT) + 1
     }
 }
 
 pub struct GitHubClient {
     auth: AuthMethod,
     cached_token: RwLock<Option<(String, Instant)>>,
-    pub rate_limit: RateLimit,
-    api_calls: AtomicUsize,
-    cache_hits: AtomicUsize,
-    cache: Arc<RwLock<ResponseCache>>,
+    pub rate_limit: Arc<RateLimit>,
+    api_calls: Arc<AtomicUsize>,
+    /// REST calls answered with something other than a 304, i.e. the ones
+    /// GitHub charges against the quota.
+    quota_used: Arc<AtomicUsize>,
+    cache_hits: Arc<AtomicUsize>,
+    cache: Arc<RwLock<DataCache>>,
+    /// Repositories fetched by `prefetch_repositories`, keyed by `owner/repo`.
+    snapshots: Arc<RwLock<HashMap<String, RepositorySnapshot>>>,
+    max_pages: usize,
+    /// Sleep until the quota is refilled instead of giving up.
+    wait_for_reset: bool,
+    /// Stop once this run has charged this many calls to the quota.
+    max_api_calls: Option<usize>,
+    concurrency: usize,
+    transport: Arc<dyn Transport>,
 }
 
 impl Clone for GitHubClient {
@@ -100,10 +114,17 @@ impl Clone for GitHubClient {
         Self {
             auth: self.auth.clone(),
             cached_token: RwLock::new(self.cached_token.read().unwrap().clone()),
-            rate_limit: self.rate_limit.clone(),
-            api_calls: AtomicUsize::new(self.api_calls.load(Ordering::SeqCst)),
-            cache_hits: AtomicUsize::new(self.cache_hits.load(Ordering::SeqCst)),
+            rate_limit: Arc::clone(&self.rate_limit),
+            api_calls: Arc::clone(&self.api_calls),
+            quota_used: Arc::clone(&self.quota_used),
+            cache_hits: Arc::clone(&self.cache_hits),
             cache: Arc::clone(&self.cache),
+            snapshots: Arc::clone(&self.snapshots),
+            max_pages: self.max_pages,
+            wait_for_reset: self.wait_for_reset,
+            max_api_calls: self.max_api_calls,
+            concurrency: self.concurrency,
+            transport: Arc::clone(&self.transport),
         }
     }
 }
@@ -117,10 +138,17 @@ impl GitHubClient {
         Self {
             auth: token.map(AuthMethod::Token).unwrap_or(AuthMethod::None),
             cached_token: RwLock::new(None),
-            rate_limit: RateLimit::new(),
-            api_calls: AtomicUsize::new(0),
-            cache_hits: AtomicUsize::new(0),
-            cache: Arc::new(RwLock::new(ResponseCache::from_data_cache(data_cache))),
+            rate_limit: Arc::new(RateLimit::new()),
+            api_calls: Arc::new(AtomicUsize::new(0)),
+            quota_used: Arc::new(AtomicUsize::new(0)),
+            cache_hits: Arc::new(AtomicUsize::new(0)),
+            cache: Arc::new(RwLock::new(data_cache)),
+            snapshots: Arc::new(RwLock::new(HashMap::new())),
+            max_pages: DEFAULT_MAX_PAGES,
+            wait_for_reset: false,
+            max_api_calls: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            transport: Arc::new(UreqTransport),
         }
     }
 
@@ -132,15 +160,86 @@ impl GitHubClient {
         Self {
             auth: AuthMethod::App(app_auth),
             cached_token: RwLock::new(None),
-            rate_limit: RateLimit::new(),
-            api_calls: AtomicUsize::new(0),
-            cache_hits: AtomicUsize::new(0),
-            cache: Arc::new(RwLock::new(ResponseCache::from_data_cache(data_cache))),
+            rate_limit: Arc::new(RateLimit::new()),
+            api_calls: Arc::new(AtomicUsize::new(0)),
+            quota_used: Arc::new(AtomicUsize::new(0)),
+            cache_hits: Arc::new(AtomicUsize::new(0)),
+            cache: Arc::new(RwLock::new(data_cache)),
+            snapshots: Arc::new(RwLock::new(HashMap::new())),
+            max_pages: DEFAULT_MAX_PAGES,
+            wait_for_reset: false,
+            max_api_calls: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            transport: Arc::new(UreqTransport),
         }
     }
 
+    /// Replace the HTTP backend, e.g. with a `FixtureTransport` in tests.
+    pub fn set_transport(&mut self, transport: Arc<dyn Transport>) {
+        self.transport = transport;
+    }
+
+    /// Cap the pages fetched for releases and contributors.
+    pub fn set_max_pages(&mut self, max_pages: usize) {
+        self.max_pages = max_pages.max(1);
+    }
+
+    /// Number of workers `execute_parallel` runs at once.
+    pub fn set_concurrency(&mut self, concurrency: usize) {
+        self.concurrency = concurrency.max(1);
+    }
+
+    /// Wait for the quota reset when the rate limit is hit, instead of
+    /// stopping the run.
+    pub fn set_wait_for_reset(&mut self, wait: bool) {
+        self.wait_for_reset = wait;
+    }
+
+    /// Cap the calls this run may charge to the hourly quota.
+    pub fn set_max_api_calls(&mut self, max: Option<usize>) {
+        self.max_api_calls = max;
+    }
+
+    pub fn budget_exhausted(&self) -> bool {
+        self.max_api_calls
+            .is_some_and(|max| self.quota_used.load(Ordering::SeqCst) >= max)
+    }
+
+    /// Whether a batch may start another item: the budget isn't spent, and
+    /// quota is left or will be waited for.
+    fn can_continue(&self) -> bool {
+        !self.budget_exhausted() && (self.wait_for_reset || self.rate_limit.has_remaining())
+    }
+
+    fn sleep_until_reset(&self) {
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        let wait = self.rate_limit.secs_until_reset(now);
+        warn!(wait_secs = wait, "Rate limit exhausted, waiting for reset");
+        thread::sleep(Duration::from_secs(wait));
+        // Unknown until the next response reports it.
+        self.rate_limit.remaining.store(usize::MAX, Ordering::SeqCst);
+    }
+
     pub fn export_data_cache(&self) -> DataCache {
-        self.cache.read().unwrap().to_data_cache()
+        self.cache.read().unwrap().clone()
+    }
+
+    /// Revisions plugins were last built from, keyed by plugin id.
+    pub fn revisions(&self) -> HashMap<String, RepoRevision> {
+        let cache = self.cache.read().unwrap();
+        cache
+            .revisions
+            .iter()
+            .map(|(id, e)| (id.clone(), e.data.clone()))
+            .collect()
+    }
+
+    /// Remember the revisions of plugins built in this run.
+    pub fn record_revisions(&self, revisions: HashMap<String, RepoRevision>) {
+        let mut cache = self.cache.write().unwrap();
+        for (id, revision) in revisions {
+            cache.revisions.insert(id, CacheEntry::new(revision, None));
+        }
     }
 
     pub fn api_calls(&self) -> usize {
@@ -171,6 +270,16 @@ impl GitHubClient {
         }
     }
 
+    fn authorization(&self) -> Result<Option<String>, GitHubError> {
+        match self.get_token() {
+            Ok(token) => Ok(token.map(|t| format!("Bearer {}", t))),
+            Err(e) => {
+                warn!(error = %e, "Failed to get token");
+                Err(GitHubError::Unauthorized)
+            }
+        }
+    }
+
     fn update_rate_limit_from_headers(
         &self,
         remaining: Option<&str>,
@@ -194,146 +303,319 @@ impl GitHubClient {
         }
     }
 
-    fn request<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, String> {
-        self.request_with_etag(url, None).map(|(data, _)| data)
-    }
-
     fn request_with_etag<T: serde::de::DeserializeOwned>(
         &self,
         url: &str,
         etag: Option<&str>,
-    ) -> Result<(T, Option<String>), String> {
+    ) -> Result<(T, Option<String>), GitHubError> {
         let _span = debug_span!("api_request", url = %url).entered();
-        let token = self.get_token()?;
+        let resp = self.send(url, ACCEPT_JSON, etag, None)?;
 
-        for attempt in 0..3 {
-            let mut req = ureq::get(url)
-                .header("Accept", "application/vnd.github+json")
-                .header("User-Agent", USER_AGENT)
-                .header("X-GitHub-Api-Version", "2022-11-28");
-
-            if let Some(t) = &token {
-                req = req.header("Authorization", &format!("Bearer {}", t));
+        match resp.status {
+            304 => {
+                self.cache_hits.fetch_add(1, Ordering::SeqCst);
+                Err(GitHubError::NotModified)
             }
-
-            if let Some(etag_val) = etag {
-                req = req.header("If-None-Match", etag_val);
+            200..=299 => {
+                let new_etag = resp.header("ETag").map(String::from);
+                let data = parse_json(&resp.body)?;
+                Ok((data, new_etag))
             }
+            code => Err(self.status_error(code)),
+        }
+    }
 
-            self.api_calls.fetch_add(1, Ordering::SeqCst);
-            match req.call() {
-                Ok(resp) if resp.status() == 304 => {
-                    self.cache_hits.fetch_add(1, Ordering::SeqCst);
-                    return Err("not_modified".to_string());
-                }
-                Ok(mut resp) => {
-                    self.update_rate_limit_from_headers(
-                        resp.headers()
-                            .get("X-RateLimit-Remaining")
-                            .and_then(|h| h.to_str().ok()),
-                        resp.headers()
-                            .get("X-RateLimit-Limit")
-                            .and_then(|h| h.to_str().ok()),
-                        resp.headers()
-                            .get("X-RateLimit-Reset")
-                            .and_then(|h| h.to_str().ok()),
-                    );
+    fn request_raw(&self, url: &str) -> Result<String, GitHubError> {
+        let _span = debug_span!("api_request_raw", url = %url).entered();
+        let resp = self.send(url, ACCEPT_RAW, None, None)?;
 
-                    let new_etag = resp
-                        .headers()
-                        .get("ETag")
-                        .and_then(|h| h.to_str().ok())
-                        .map(String::from);
+        match resp.status {
+            200..=299 => Ok(resp.body),
+            code => Err(self.status_error(code)),
+        }
+    }
 
-                    let data = resp
-                        .body_mut()
-                        .read_json()
-                        .map_err(|e| format!("Parse error: {}", e))?;
+    fn request_json_cached<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<T, GitHubError> {
+        let resp = self.request_cached(url, ACCEPT_JSON)?;
+        parse_json(&resp.body)
+    }
 
-                    return Ok((data, new_etag));
-                }
-                Err(ureq::Error::StatusCode(304)) => {
+    fn status_error(&self, status: u16) -> GitHubError {
+        let reset = Some(self.rate_limit.reset() as u64).filter(|r| *r > 0);
+        GitHubError::from_status(status, reset)
+    }
+
+    /// Collect a listing by following `rel="next"` links from `url`, up to
+    /// `max_pages` pages. A failure on any page fails the whole listing, so
+    /// callers never mistake a partial listing for a complete one.
+    fn request_pages<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<Vec<T>, GitHubError> {
+        let mut items = Vec::new();
+        let mut next = Some(url.to_string());
+        let mut pages = 0;
+
+        while let Some(page_url) = next {
+            if pages == self.max_pages {
+                debug!(url = %url, pages = pages, "Page cap reached");
+                break;
+            }
+            let page = self.request_cached(&page_url, ACCEPT_JSON)?;
+            let page_items: Vec<T> = parse_json(&page.body)?;
+            items.extend(page_items);
+            next = page.link.as_deref().and_then(next_link);
+            pages += 1;
+        }
+
+        Ok(items)
+    }
+
+    /// GET `url` conditionally against the body stored for it in the cache.
+    ///
+    /// A 304 serves the stored body and, unlike a 200, doesn't count against
+    /// the rate limit. The stored body is also used when the request fails
+    /// on the network or with a server error. Rate limits, bad credentials
+    /// and a spent call budget stay errors, and a 404 drops the stored body.
+    fn request_cached(&self, url: &str, accept: &str) -> Result<CachedResponse, GitHubError> {
+        let _span = debug_span!("api_request_cached", url = %url).entered();
+        let cached = {
+            let mut cache = self.cache.write().unwrap();
+            cache.responses.get_mut(url).map(|e| {
+                e.touch();
+                e.clone()
+            })
+        };
+
+        let result = self.send(
+            url,
+            accept,
+            cached.as_ref().and_then(|e| e.etag.as_deref()),
+            cached.as_ref().and_then(|e| e.last_modified.as_deref()),
+        );
+        let error = match result {
+            Ok(resp) => match resp.status {
+                304 if cached.is_some() => {
                     self.cache_hits.fetch_add(1, Ordering::SeqCst);
-                    return Err("not_modified".to_string());
+                    debug!(url = %url, "Cache hit (304)");
+                    return Ok(cached.unwrap().data);
                 }
-                Err(ureq::Error::StatusCode(code)) if code == 403 || code == 429 => {
-                    if attempt < 2 {
-                        let wait = 30u64 * (1 << attempt);
-                        warn!(
-                            code = code,
-                            wait_secs = wait,
-                            attempt = attempt + 1,
-                            "Rate limited, exponential backoff"
-                        );
-                        thread::sleep(Duration::from_secs(wait));
-                        continue;
+                200..=299 => {
+                    let data = CachedResponse {
+                        link: resp.header("Link").map(String::from),
+                        body: resp.body,
+                    };
+                    let mut entry =
+                        CacheEntry::new(data.clone(), resp.headers.get("etag").cloned());
+                    entry.last_modified = resp.headers.get("last-modified").cloned();
+                    if entry.etag.is_some() || entry.last_modified.is_some() {
+                        let mut cache = self.cache.write().unwrap();
+                        cache.responses.insert(url.to_string(), entry);
                     }
-                    return Err(format!("Rate limited after {} attempts", attempt + 1));
+                    return Ok(data);
+                }
+                404 => {
+                    self.cache.write().unwrap().responses.remove(url);
+                    return Err(GitHubError::NotFound);
                 }
-                Err(e) => return Err(format!("HTTP error: {}", e)),
+                code => self.status_error(code),
+            },
+            Err(e) => e,
+        };
+
+        match cached {
+            Some(entry) if error.is_transient() => {
+                warn!(url = %url, error = %error, "API failed, using cached response");
+                Ok(entry.data)
             }
+            _ => Err(error),
         }
-        Err("Max retries exceeded".to_string())
     }
 
-    fn request_raw(&self, url: &str) -> Result<String, String> {
-        let _span = debug_span!("api_request_raw", url = %url).entered();
-        let token = self.get_token()?;
+    /// Send a GET through the transport, retrying with exponential backoff
+    /// while GitHub answers 403/429.
+    fn send(
+        &self,
+        url: &str,
+        accept: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<TransportResponse, GitHubError> {
+        let authorization = self.authorization()?;
+
+        let mut headers = vec![
+            ("Accept", accept),
+            ("User-Agent", USER_AGENT),
+            ("X-GitHub-Api-Version", "2022-11-28"),
+        ];
+        if let Some(auth) = &authorization {
+            headers.push(("Authorization", auth.as_str()));
+        }
+        if let Some(etag_val) = etag {
+            headers.push(("If-None-Match", etag_val));
+        } else if let Some(since) = last_modified {
+            headers.push(("If-Modified-Since", since));
+        }
 
-        for attempt in 0..3 {
-            let mut req = ureq::get(url)
-                .header("Accept", "application/vnd.github.raw+json")
-                .header("User-Agent", USER_AGENT)
-                .header("X-GitHub-Api-Version", "2022-11-28");
+        self.with_retry(true, || self.transport.get(url, &headers))
+    }
+
+    /// POST a GraphQL query. GraphQL has its own point budget, so its
+    /// rate-limit headers don't update `rate_limit`, which tracks REST.
+    fn send_graphql(&self, query: &str) -> Result<TransportResponse, GitHubError> {
+        let authorization = self.authorization()?;
+        let body = serde_json::json!({ "query": query }).to_string();
+
+        let mut headers = vec![
+            ("Accept", ACCEPT_JSON),
+            ("Content-Type", "application/json"),
+            ("User-Agent", USER_AGENT),
+        ];
+        if let Some(auth) = &authorization {
+            headers.push(("Authorization", auth.as_str()));
+        }
 
-            if let Some(t) = &token {
-                req = req.header("Authorization", &format!("Bearer {}", t));
+        self.with_retry(false, || self.transport.post(GRAPHQL_URL, &headers, &body))
+    }
+
+    fn with_retry(
+        &self,
+        track_rate_limit: bool,
+        call: impl Fn() -> Result<TransportResponse, String>,
+    ) -> Result<TransportResponse, GitHubError> {
+        for attempt in 0..3 {
+            if self.budget_exhausted() {
+                debug!(max = self.max_api_calls.unwrap_or_default(), "API call budget exhausted");
+                return Err(GitHubError::RateLimited { reset: None });
             }
 
             self.api_calls.fetch_add(1, Ordering::SeqCst);
-            match req.call() {
-                Ok(mut resp) => {
-                    self.update_rate_limit_from_headers(
-                        resp.headers()
-                            .get("X-RateLimit-Remaining")
-                            .and_then(|h| h.to_str().ok()),
-                        resp.headers()
-                            .get("X-RateLimit-Limit")
-                            .and_then(|h| h.to_str().ok()),
-                        resp.headers()
-                            .get("X-RateLimit-Reset")
-                            .and_then(|h| h.to_str().ok()),
+            let resp = call().map_err(GitHubError::Network)?;
+
+            if track_rate_limit && resp.status != 304 {
+                self.quota_used.fetch_add(1, Ordering::SeqCst);
+                self.update_rate_limit_from_headers(
+                    resp.header("X-RateLimit-Remaining"),
+                    resp.header("X-RateLimit-Limit"),
+                    resp.header("X-RateLimit-Reset"),
+                );
+            }
+
+            if resp.status == 403 || resp.status == 429 {
+                if attempt < 2
+                    && let Some(wait) = self.rate_limit_wait(&resp, attempt)
+                {
+                    warn!(
+                        code = resp.status,
+                        wait_secs = wait,
+                        attempt = attempt + 1,
+                        "Rate limited, waiting before retry"
                     );
-                    return resp.body_mut().read_to_string().map_err(|e| e.to_string());
+                    thread::sleep(Duration::from_secs(wait));
+                    continue;
                 }
-                Err(ureq::Error::StatusCode(404)) => return Err("not found".to_string()),
-                Err(ureq::Error::StatusCode(code)) if code == 403 || code == 429 => {
-                    if attempt < 2 {
-                        let wait = 30u64 * (1 << attempt);
-                        warn!(
-                            code = code,
-                            wait_secs = wait,
-                            attempt = attempt + 1,
-                            "Rate limited, exponential backoff"
-                        );
-                        thread::sleep(Duration::from_secs(wait));
-                        continue;
+                debug!(attempts = attempt + 1, "Giving up on rate limit");
+                return Err(self.status_error(resp.status));
+            }
+            if resp.status == 401 {
+                return Err(GitHubError::Unauthorized);
+            }
+
+            return Ok(resp);
+        }
+        Err(self.status_error(429))
+    }
+
+    /// How long to wait before retrying a 403/429, or `None` to give up.
+    ///
+    /// `Retry-After` (secondary limits) is always honoured. An exhausted
+    /// primary quota is only waited out with `wait_for_reset`, since nothing
+    /// short of the reset helps. Anything else backs off exponentially.
+    fn rate_limit_wait(&self, resp: &TransportResponse, attempt: u32) -> Option<u64> {
+        if let Some(secs) = resp.header("Retry-After").and_then(|s| s.trim().parse().ok()) {
+            return Some(secs);
+        }
+        if resp.header("X-RateLimit-Remaining") == Some("0") {
+            let now = chrono::Utc::now().timestamp().max(0) as u64;
+            return self
+                .wait_for_reset
+                .then(|| self.rate_limit.secs_until_reset(now));
+        }
+        Some(30u64 * (1 << attempt))
+    }
+
+    /// Fetch metadata, topics, license, default-branch tree OID, releases
+    /// and README of many repositories with one GraphQL query per
+    /// `GRAPHQL_BATCH_SIZE` of them.
+    ///
+    /// Later `get_repository`, `get_releases`, `get_readme` and `get_tree`
+    /// calls for these repositories are answered from the result. Batches
+    /// that fail are logged and skipped, leaving their repositories to the
+    /// REST endpoints. Returns how many repositories were prefetched.
+    pub fn prefetch_repositories(&self, repos: &[(String, String)]) -> usize {
+        let _span = info_span!("prefetch_repositories", total = repos.len()).entered();
+        let mut prefetched = 0;
+
+        for chunk in repos.chunks(GRAPHQL_BATCH_SIZE) {
+            let result = self
+                .send_graphql(&repositories_query(chunk))
+                .map_err(|e| e.to_string())
+                .and_then(|resp| match resp.status {
+                    200..=299 => parse_repositories_response(&resp.body, chunk.len()),
+                    code => Err(self.status_error(code).to_string()),
+                });
+
+            let snapshots = match result {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!(error = %e, repos = chunk.len(), "GraphQL batch failed, falling back to REST");
+                    continue;
+                }
+            };
+
+            let mut cache = self.snapshots.write().unwrap();
+            for ((owner, repo), snapshot) in chunk.iter().zip(snapshots) {
+                match snapshot {
+                    Some(s) => {
+                        cache.insert(format!("{}/{}", owner, repo), s);
+                        prefetched += 1;
                     }
-                    return Err(format!("Rate limited after {} attempts", attempt + 1));
+                    None => debug!(repo = %format!("{}/{}", owner, repo), "Not resolved by GraphQL"),
                 }
-                Err(e) => return Err(format!("HTTP error: {}", e)),
             }
         }
-        Err("Max retries exceeded".to_string())
+
+        info!(prefetched = prefetched, total = repos.len(), "Prefetched repositories");
+        prefetched
     }
 
-    pub fn get_repository(&self, owner: &str, repo: &str) -> Result<Repository, String> {
+    fn with_snapshot<R>(
+        &self,
+        owner: &str,
+        repo: &str,
+        f: impl FnOnce(&RepositorySnapshot) -> Option<R>,
+    ) -> Option<R> {
+        let snapshots = self.snapshots.read().unwrap();
+        snapshots.get(&format!("{}/{}", owner, repo)).and_then(f)
+    }
+
+    pub fn get_repository(&self, owner: &str, repo: &str) -> Result<Repository, GitHubError> {
+        if let Some(data) = self.with_snapshot(owner, repo, |s| Some(s.repository.clone())) {
+            return Ok(data);
+        }
+
         let cache_key = format!("{}/{}", owner, repo);
         let url = format!("{}/repos/{}/{}", API_BASE, owner, repo);
 
         let cached = {
-            let cache = self.cache.read().unwrap();
-            cache.repositories.get(&cache_key).cloned()
+            let mut cache = self.cache.write().unwrap();
+            cache.repositories.get_mut(&cache_key).map(|e| {
+                e.touch();
+                e.clone()
+            })
         };
 
         let etag = cached.as_ref().and_then(|e| e.etag.as_deref());
@@ -341,85 +623,144 @@ impl GitHubClient {
         match self.request_with_etag::<Repository>(&url, etag) {
             Ok((data, new_etag)) => {
                 let mut cache = self.cache.write().unwrap();
-                cache.repositories.insert(
-                    cache_key,
-                    CacheEntry {
-                        data: data.clone(),
-                        etag: new_etag,
-                    },
-                );
+                cache
+                    .repositories
+                    .insert(cache_key, CacheEntry::new(data.clone(), new_etag));
                 Ok(data)
             }
-            Err(e) if e == "not_modified" => {
+            Err(GitHubError::NotModified) => {
                 debug!(key = %cache_key, "Cache hit (304)");
                 Ok(cached.unwrap().data)
             }
-            Err(e) => {
-                if let Some(entry) = cached {
+            Err(GitHubError::NotFound) => {
+                self.cache.write().unwrap().repositories.remove(&cache_key);
+                Err(GitHubError::NotFound)
+            }
+            Err(e) => match cached {
+                Some(entry) if e.is_transient() => {
                     warn!(key = %cache_key, error = %e, "API failed, using cached repository");
                     Ok(entry.data)
-                } else {
-                    Err(e)
                 }
-            }
+                _ => Err(e),
+            },
         }
     }
 
-    pub fn get_latest_release(&self, owner: &str, repo: &str) -> Result<Release, String> {
+    pub fn get_latest_release(&self, owner: &str, repo: &str) -> Result<Release, GitHubError> {
         let url = format!("{}/repos/{}/{}/releases/latest", API_BASE, owner, repo);
-        self.request(&url)
+        self.request_json_cached(&url)
     }
 
-    pub fn get_releases(&self, owner: &str, repo: &str) -> Result<Vec<Release>, String> {
-        let url = format!("{}/repos/{}/{}/releases?per_page=30", API_BASE, owner, repo);
-        self.request(&url)
+    pub fn get_releases(&self, owner: &str, repo: &str) -> Result<Vec<Release>, GitHubError> {
+        if let Some(data) = self.with_snapshot(owner, repo, |s| s.releases.clone()) {
+            return Ok(data);
+        }
+        let url = format!("{}/repos/{}/{}/releases?per_page=100", API_BASE, owner, repo);
+        self.request_pages(&url)
     }
 
-    pub fn search_repositories(&self, query: &str, page: u32) -> Result<SearchResult, String> {
+    pub fn search_repositories(&self, query: &str, page: u32) -> Result<SearchResult, GitHubError> {
         let url = format!(
             "{}/search/repositories?q={}&per_page=100&page={}",
             API_BASE,
             urlencoded(query),
             page
         );
-        self.request(&url)
+        self.request_json_cached(&url)
     }
 
-    pub fn search_code(&self, query: &str, page: u32) -> Result<CodeSearchResult, String> {
+    pub fn search_code(&self, query: &str, page: u32) -> Result<CodeSearchResult, GitHubError> {
         let url = format!(
             "{}/search/code?q={}&per_page=100&page={}",
             API_BASE,
             urlencoded(query),
             page
         );
-        self.request(&url)
+        self.request_json_cached(&url)
     }
 
-    pub fn get_readme(&self, owner: &str, repo: &str) -> Result<String, String> {
+    pub fn get_readme(&self, owner: &str, repo: &str) -> Result<String, GitHubError> {
+        if let Some(data) = self.with_snapshot(owner, repo, |s| s.readme.clone()) {
+            return Ok(data);
+        }
         let url = format!("{}/repos/{}/{}/readme", API_BASE, owner, repo);
-        match self.request_raw(&url) {
-            Ok(s) => Ok(s),
-            Err(e) if e == "not found" => Ok(String::new()),
+        match self.request_cached(&url, ACCEPT_RAW) {
+            Ok(resp) => Ok(resp.body),
+            Err(GitHubError::NotFound) => Ok(String::new()),
             Err(e) => Err(e),
         }
     }
 
+    /// README whose blob SHA in the tree is `sha`, served from the blob cache
+    /// when the same README was fetched before.
+    pub fn get_readme_blob(&self, owner: &str, repo: &str, sha: &str) -> Result<String, GitHubError> {
+        if let Some(content) = self.cached_blob(sha) {
+            return Ok(content);
+        }
+        let content = self.get_readme(owner, repo)?;
+        if !content.is_empty() {
+            self.store_blob(sha, &content);
+        }
+        Ok(content)
+    }
+
     pub fn repository_exists(&self, owner: &str, repo: &str) -> bool {
         self.get_repository(owner, repo).is_ok()
     }
 
-    pub fn get_file_content(&self, owner: &str, repo: &str, path: &str) -> Result<String, String> {
+    pub fn get_file_content(&self, owner: &str, repo: &str, path: &str) -> Result<String, GitHubError> {
         let url = format!("{}/repos/{}/{}/contents/{}", API_BASE, owner, repo, path);
         self.request_raw(&url)
     }
 
+    /// Content of the file at `path` whose blob SHA is `sha`. Blobs are
+    /// immutable, so a cached copy never needs revalidating.
+    pub fn get_blob(
+        &self,
+        owner: &str,
+        repo: &str,
+        path: &str,
+        sha: &str,
+    ) -> Result<String, GitHubError> {
+        if let Some(content) = self.cached_blob(sha) {
+            return Ok(content);
+        }
+        let content = self.get_file_content(owner, repo, path)?;
+        self.store_blob(sha, &content);
+        Ok(content)
+    }
+
+    fn cached_blob(&self, sha: &str) -> Option<String> {
+        if sha.is_empty() {
+            return None;
+        }
+        let content = {
+            let mut cache = self.cache.write().unwrap();
+            let entry = cache.blobs.get_mut(sha)?;
+            entry.touch();
+            entry.data.clone()
+        };
+        self.cache_hits.fetch_add(1, Ordering::SeqCst);
+        debug!(sha = %sha, "Cache hit (blob)");
+        Some(content)
+    }
+
+    fn store_blob(&self, sha: &str, content: &str) {
+        if !sha.is_empty() {
+            let mut cache = self.cache.write().unwrap();
+            cache
+                .blobs
+                .insert(sha.to_string(), CacheEntry::new(content.to_string(), None));
+        }
+    }
+
     pub fn get_file_content_at_ref(
         &self,
         owner: &str,
         repo: &str,
         path: &str,
         git_ref: &str,
-    ) -> Result<String, String> {
+    ) -> Result<String, GitHubError> {
         let url = format!(
             "{}/repos/{}/{}/contents/{}?ref={}",
             API_BASE,
@@ -436,9 +777,9 @@ impl GitHubClient {
         owner: &str,
         repo: &str,
         path: &str,
-    ) -> Result<Vec<String>, String> {
+    ) -> Result<Vec<String>, GitHubError> {
         let url = format!("{}/repos/{}/{}/contents/{}", API_BASE, owner, repo, path);
-        let items: Vec<ContentItem> = self.request(&url)?;
+        let items: Vec<ContentItem> = self.request_json_cached(&url)?;
         Ok(items
             .into_iter()
             .filter(|i| i.item_type == "dir")
@@ -446,7 +787,7 @@ impl GitHubClient {
             .collect())
     }
 
-    pub fn get_tree(&self, owner: &str, repo: &str, branch: &str) -> Result<GitTree, String> {
+    pub fn get_tree(&self, owner: &str, repo: &str, branch: &str) -> Result<GitTree, GitHubError> {
         let cache_key = format!("{}/{}/{}", owner, repo, branch);
         let url = format!(
             "{}/repos/{}/{}/git/trees/{}?recursive=1",
@@ -454,126 +795,175 @@ impl GitHubClient {
         );
 
         let cached = {
-            let cache = self.cache.read().unwrap();
-            cache.trees.get(&cache_key).cloned()
+            let mut cache = self.cache.write().unwrap();
+            cache.trees.get_mut(&cache_key).map(|e| {
+                e.touch();
+                e.clone()
+            })
         };
 
+        // The prefetched tree OID tells whether the cached tree is current
+        // without spending a request on revalidating it.
+        if let Some(entry) = &cached
+            && self
+                .with_snapshot(owner, repo, |s| {
+                    s.tree_oid.as_ref().map(|oid| {
+                        *oid == entry.data.sha
+                            && s.repository.default_branch.as_deref() == Some(branch)
+                    })
+                })
+                .unwrap_or(false)
+        {
+            self.cache_hits.fetch_add(1, Ordering::SeqCst);
+            debug!(key = %cache_key, "Cache hit (tree OID)");
+            return Ok(entry.data.clone());
+        }
+
         let etag = cached.as_ref().and_then(|e| e.etag.as_deref());
 
         match self.request_with_etag::<GitTree>(&url, etag) {
-            Ok((data, new_etag)) => {
+            Ok((mut data, new_etag)) => {
+                if data.truncated {
+                    debug!(key = %cache_key, "Recursive tree truncated, walking sub-trees");
+                    data = self.walk_tree(owner, repo, &data.sha)?;
+                }
                 let mut cache = self.cache.write().unwrap();
-                cache.trees.insert(
-                    cache_key,
-                    CacheEntry {
-                        data: data.clone(),
-                        etag: new_etag,
-                    },
-                );
+                cache
+                    .trees
+                    .insert(cache_key, CacheEntry::new(data.clone(), new_etag));
                 Ok(data)
             }
-            Err(e) if e == "not_modified" => {
+            Err(GitHubError::NotModified) => {
                 debug!(key = %cache_key, "Cache hit (304)");
                 Ok(cached.unwrap().data)
             }
-            Err(e) => {
-                if let Some(entry) = cached {
+            Err(GitHubError::NotFound) => {
+                self.cache.write().unwrap().trees.remove(&cache_key);
+                Err(GitHubError::NotFound)
+            }
+            Err(e) => match cached {
+                Some(entry) if e.is_transient() => {
                     warn!(key = %cache_key, error = %e, "API failed, using cached tree");
                     Ok(entry.data)
-                } else {
-                    Err(e)
                 }
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Assemble the full tree under `sha` one directory at a time, for
+    /// repositories too large for a single recursive response. Gives up
+    /// after `MAX_TREE_WALK` requests and returns the tree marked truncated.
+    fn walk_tree(&self, owner: &str, repo: &str, sha: &str) -> Result<GitTree, GitHubError> {
+        let mut entries = Vec::new();
+        let mut pending = vec![(String::new(), sha.to_string())];
+        let mut requests = 0;
+
+        while let Some((prefix, tree_sha)) = pending.pop() {
+            if requests == MAX_TREE_WALK {
+                warn!(repo = %format!("{}/{}", owner, repo), "Tree walk limit reached");
+                return Ok(GitTree {
+                    sha: sha.to_string(),
+                    tree: entries,
+                    truncated: true,
+                });
+            }
+            let url = format!(
+                "{}/repos/{}/{}/git/trees/{}",
+                API_BASE, owner, repo, tree_sha
+            );
+            let subtree: GitTree = self.request_json_cached(&url)?;
+            requests += 1;
+
+            for mut entry in subtree.tree {
+                if !prefix.is_empty() {
+                    entry.path = format!("{}/{}", prefix, entry.path);
+                }
+                if entry.entry_type == "tree" {
+                    pending.push((entry.path.clone(), entry.sha.clone()));
+                }
+                entries.push(entry);
             }
         }
+
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(GitTree {
+            sha: sha.to_string(),
+            tree: entries,
+            truncated: false,
+        })
     }
 
-    pub fn get_contributors_by_url(&self, url: &str) -> Result<Vec<Contributor>, String> {
+    pub fn get_contributors_by_url(&self, url: &str) -> Result<Vec<Contributor>, GitHubError> {
         if url.is_empty() {
             return Ok(Vec::new());
         }
-        self.request(url)
+        self.request_pages(&format!("{}?per_page=100", url))
     }
 
+    /// Run `handler` over `items` on a pool of `concurrency` workers pulling
+    /// from a shared queue, paced by a token bucket. Results come back in
+    /// input order; items left over when the rate limit or budget stops the
+    /// batch are not run.
     pub fn execute_parallel<T, R, F>(&self, items: Vec<T>, handler: F) -> BatchResult<R>
     where
-        T: Send + Clone + 'static,
-        R: Send + std::fmt::Debug + 'static,
-        F: Fn(T, &GitHubClient) -> R + Send + Sync + 'static,
+        T: Send,
+        R: Send,
+        F: Fn(T, &GitHubClient) -> R + Sync,
     {
-        let _span = info_span!("execute_parallel", total = items.len()).entered();
-        let results: Arc<Mutex<Vec<R>>> = Arc::new(Mutex::new(Vec::new()));
-        let client = Arc::new(self.clone());
-        let handler = Arc::new(handler);
-        let stop_flag = Arc::new(AtomicBool::new(false));
-        let processed_count = Arc::new(AtomicUsize::new(0));
         let total = items.len();
-        let num_chunks = total.div_ceil(MAX_CONCURRENT);
-
-        for (chunk_idx, chunk) in items.chunks(MAX_CONCURRENT).enumerate() {
-            if stop_flag.load(Ordering::SeqCst) {
-                break;
-            }
-
-            let _chunk_span = debug_span!(
-                "chunk",
-                idx = chunk_idx,
-                of = num_chunks,
-                size = chunk.len()
-            )
-            .entered();
-            let mut handles = Vec::new();
-
-            for item in chunk {
-                if stop_flag.load(Ordering::SeqCst) {
-                    break;
-                }
-
-                let item = item.clone();
-                let client = Arc::clone(&client);
-                let handler = Arc::clone(&handler);
-                let results = Arc::clone(&results);
-                let stop_flag = Arc::clone(&stop_flag);
-                let processed_count = Arc::clone(&processed_count);
-
-                let handle = thread::spawn(move || {
-                    if stop_flag.load(Ordering::SeqCst) {
-                        return;
-                    }
-
-                    if !client.rate_limit.has_remaining() {
-                        stop_flag.store(true, Ordering::SeqCst);
-                        return;
-                    }
+        let workers = self.concurrency.min(total).max(1);
+        let _span = info_span!("execute_parallel", total = total, workers = workers).entered();
+        let queue = Mutex::new(items.into_iter().enumerate());
+        let results: Mutex<Vec<(usize, R)>> = Mutex::new(Vec::with_capacity(total));
+        let bucket = TokenBucket::new(workers, ITEMS_PER_SEC);
+        let stop_flag = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            for worker in 0..workers {
+                let (queue, results, bucket, stop_flag, handler) =
+                    (&queue, &results, &bucket, &stop_flag, &handler);
+                scope.spawn(move || {
+                    let _worker_span = debug_span!("worker", idx = worker).entered();
+                    loop {
+                        if stop_flag.load(Ordering::SeqCst) {
+                            return;
+                        }
+                        if self.wait_for_reset && !self.rate_limit.has_remaining() {
+                            self.sleep_until_reset();
+                        }
+                        if !self.can_continue() {
+                            stop_flag.store(true, Ordering::SeqCst);
+                            return;
+                        }
 
-                    let result = handler(item, &client);
+                        bucket.acquire();
+                        let Some((idx, item)) = queue.lock().unwrap().next() else {
+                            return;
+                        };
+                        let result = handler(item, self);
 
-                    if !client.rate_limit.has_remaining() {
-                        stop_flag.store(true, Ordering::SeqCst);
+                        let mut results = results.lock().unwrap();
+                        results.push((idx, result));
+                        debug!(processed = results.len(), total = total, "Item completed");
                     }
-
-                    results.lock().unwrap().push(result);
-                    processed_count.fetch_add(1, Ordering::SeqCst);
                 });
-                handles.push(handle);
-            }
-
-            for handle in handles {
-                let _ = handle.join();
             }
+        });
 
-            let current = processed_count.load(Ordering::SeqCst);
-            debug!(processed = current, total = total, "Chunk completed");
-
-            if chunk_idx < num_chunks - 1 && !stop_flag.load(Ordering::SeqCst) {
-                thread::sleep(Duration::from_millis(1500));
-            }
-        }
-
-        let results = Arc::try_unwrap(results).unwrap().into_inner().unwrap();
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(idx, _)| *idx);
+        let results: Vec<R> = results.into_iter().map(|(_, r)| r).collect();
         let processed = results.len();
-        let stopped = stop_flag.load(Ordering::SeqCst);
+        let stopped = stop_flag.load(Ordering::SeqCst) && processed < total;
 
-        if stopped {
+        if stopped && self.budget_exhausted() {
+            warn!(
+                processed = processed,
+                total = total,
+                "Batch stopped by API call budget"
+            );
+        } else if stopped {
             warn!(
                 processed = processed,
                 total = total,
@@ -597,6 +987,21 @@ pub struct BatchResult<R> {
     pub stopped_by_rate_limit: bool,
 }
 
+fn parse_json<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, GitHubError> {
+    serde_json::from_str(body).map_err(|e| GitHubError::Parse(e.to_string()))
+}
+
+/// URL of the `rel="next"` entry of a `Link` header.
+fn next_link(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == r#"rel="next""#)
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
 fn urlencoded(s: &str) -> String {
     let mut result = String::with_capacity(s.len() * 3);
     for c in s.chars() {
@@ -613,3 +1018,369 @@ fn urlencoded(s: &str) -> String {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Answers 200 with an ETag, or 304 when that ETag is sent back.
+    struct EtagTransport;
+
+    impl Transport for EtagTransport {
+        fn get(&self, _url: &str, headers: &[(&str, &str)]) -> Result<TransportResponse, String> {
+            let revalidating = headers
+                .iter()
+                .any(|(name, value)| *name == "If-None-Match" && *value == "\"v1\"");
+            let (status, body) = if revalidating {
+                (304, String::new())
+            } else {
+                (200, r#"[{"id":1,"tag_name":"v1.0.0","name":null}]"#.to_string())
+            };
+            Ok(TransportResponse {
+                status,
+                headers: HashMap::from([("etag".to_string(), "\"v1\"".to_string())]),
+                body,
+            })
+        }
+
+        fn post(
+            &self,
+            _url: &str,
+            _headers: &[(&str, &str)],
+            _body: &str,
+        ) -> Result<TransportResponse, String> {
+            Err("unsupported".to_string())
+        }
+    }
+
+    /// Serves fixed responses by URL; anything else is a 404.
+    struct MapTransport(HashMap<&'static str, (&'static str, Option<&'static str>)>);
+
+    impl Transport for MapTransport {
+        fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<TransportResponse, String> {
+            let Some((body, link)) = self.0.get(url) else {
+                return Ok(TransportResponse {
+                    status: 404,
+                    headers: HashMap::new(),
+                    body: String::new(),
+                });
+            };
+            let mut headers = HashMap::from([("etag".to_string(), format!("\"{}\"", url))]);
+            if let Some(link) = link {
+                headers.insert("link".to_string(), link.to_string());
+            }
+            Ok(TransportResponse {
+                status: 200,
+                headers,
+                body: body.to_string(),
+            })
+        }
+
+        fn post(
+            &self,
+            _url: &str,
+            _headers: &[(&str, &str)],
+            _body: &str,
+        ) -> Result<TransportResponse, String> {
+            Err("unsupported".to_string())
+        }
+    }
+
+    /// Plays back a fixed sequence of responses, whatever the URL.
+    struct SequenceTransport(Mutex<Vec<TransportResponse>>);
+
+    impl SequenceTransport {
+        fn new(responses: Vec<(u16, &[(&str, &str)])>) -> Self {
+            let responses = responses
+                .into_iter()
+                .rev()
+                .map(|(status, headers)| TransportResponse {
+                    status,
+                    headers: headers
+                        .iter()
+                        .map(|(k, v)| (k.to_lowercase(), v.to_string()))
+                        .collect(),
+                    body: "[]".to_string(),
+                })
+                .collect();
+            Self(Mutex::new(responses))
+        }
+    }
+
+    impl Transport for SequenceTransport {
+        fn get(&self, _url: &str, _headers: &[(&str, &str)]) -> Result<TransportResponse, String> {
+            self.0
+                .lock()
+                .unwrap()
+                .pop()
+                .ok_or_else(|| "no more responses".to_string())
+        }
+
+        fn post(
+            &self,
+            _url: &str,
+            _headers: &[(&str, &str)],
+            _body: &str,
+        ) -> Result<TransportResponse, String> {
+            Err("unsupported".to_string())
+        }
+    }
+
+    /// Answers each URL once with an ETag, then 404 as if it was deleted.
+    struct DeletedTransport(Mutex<HashSet<String>>);
+
+    impl Transport for DeletedTransport {
+        fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<TransportResponse, String> {
+            if !self.0.lock().unwrap().insert(url.to_string()) {
+                return Ok(TransportResponse {
+                    status: 404,
+                    headers: HashMap::new(),
+                    body: String::new(),
+                });
+            }
+            let body = if url.contains("/git/trees/") {
+                r#"{"sha":"root","tree":[],"truncated":false}"#
+            } else {
+                r#"{"id":1,"full_name":"o/r","name":"r","html_url":"https://github.com/o/r",
+                    "owner":{"login":"o","avatar_url":"","html_url":""}}"#
+            };
+            Ok(TransportResponse {
+                status: 200,
+                headers: HashMap::from([("etag".to_string(), "\"v1\"".to_string())]),
+                body: body.to_string(),
+            })
+        }
+
+        fn post(
+            &self,
+            _url: &str,
+            _headers: &[(&str, &str)],
+            _body: &str,
+        ) -> Result<TransportResponse, String> {
+            Err("unsupported".to_string())
+        }
+    }
+
+    #[test]
+    fn test_retry_after_is_honoured() {
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(SequenceTransport::new(vec![
+            (429, &[("Retry-After", "0")]),
+            (200, &[]),
+        ])));
+
+        assert!(client.get_releases("o", "r").unwrap().is_empty());
+        assert_eq!(client.api_calls(), 2);
+    }
+
+    #[test]
+    fn test_exhausted_quota_fails_fast_without_waiting() {
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(SequenceTransport::new(vec![(
+            403,
+            &[("X-RateLimit-Remaining", "0"), ("X-RateLimit-Reset", "4102444800")],
+        )])));
+
+        assert_eq!(
+            client.get_releases("o", "r").unwrap_err(),
+            GitHubError::RateLimited {
+                reset: Some(4102444800)
+            }
+        );
+        assert_eq!(client.api_calls(), 1);
+    }
+
+    #[test]
+    fn test_api_call_budget() {
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(SequenceTransport::new(vec![(200, &[]), (200, &[])])));
+        client.set_max_api_calls(Some(1));
+
+        assert!(client.get_releases("o", "r").is_ok());
+        assert!(client.budget_exhausted());
+        assert_eq!(
+            client.get_releases("o", "other").unwrap_err(),
+            GitHubError::RateLimited { reset: None }
+        );
+        assert_eq!(client.api_calls(), 1);
+    }
+
+    #[test]
+    fn test_secs_until_reset() {
+        let rate_limit = RateLimit::new();
+        rate_limit.reset.store(1_000, Ordering::SeqCst);
+
+        assert_eq!(rate_limit.secs_until_reset(940), 61);
+        assert_eq!(rate_limit.secs_until_reset(2_000), 1);
+        assert_eq!(rate_limit.secs_until_reset(0), 1_001);
+        rate_limit.reset.store(100_000, Ordering::SeqCst);
+        assert_eq!(rate_limit.secs_until_reset(0), MAX_RESET_WAIT + 1);
+    }
+
+    #[test]
+    fn test_execute_parallel_keeps_input_order() {
+        let mut client = GitHubClient::new(None);
+        client.set_concurrency(3);
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let batch = client.execute_parallel((0..12u64).collect(), |n, _| {
+            let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            // Early items finish last.
+            thread::sleep(Duration::from_millis(12 - n));
+            active.fetch_sub(1, Ordering::SeqCst);
+            n * 2
+        });
+
+        assert_eq!(batch.results, (0..12).map(|n| n * 2).collect::<Vec<_>>());
+        assert_eq!(batch.processed, 12);
+        assert!(!batch.stopped_by_rate_limit);
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn test_next_link() {
+        let link = r#"<https://api.github.com/x?page=2>; rel="next", <https://api.github.com/x?page=5>; rel="last""#;
+        assert_eq!(
+            next_link(link).as_deref(),
+            Some("https://api.github.com/x?page=2")
+        );
+        assert_eq!(next_link(r#"<https://api.github.com/x?page=1>; rel="prev""#), None);
+    }
+
+    #[test]
+    fn test_releases_follow_pages_up_to_cap() {
+        let first = "https://api.github.com/repos/o/r/releases?per_page=100";
+        let second = "https://api.github.com/repos/o/r/releases?per_page=100&page=2";
+        let third = "https://api.github.com/repos/o/r/releases?per_page=100&page=3";
+        let transport = MapTransport(HashMap::from([
+            (
+                first,
+                (
+                    r#"[{"id":1,"tag_name":"v3","name":null}]"#,
+                    Some(r#"<https://api.github.com/repos/o/r/releases?per_page=100&page=2>; rel="next""#),
+                ),
+            ),
+            (
+                second,
+                (
+                    r#"[{"id":2,"tag_name":"v2","name":null}]"#,
+                    Some(r#"<https://api.github.com/repos/o/r/releases?per_page=100&page=3>; rel="next""#),
+                ),
+            ),
+            (third, (r#"[{"id":3,"tag_name":"v1","name":null}]"#, None)),
+        ]));
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(transport));
+
+        assert_eq!(client.get_releases("o", "r").unwrap().len(), 3);
+
+        client.set_max_pages(2);
+        let capped = client.get_releases("o", "r").unwrap();
+        assert_eq!(
+            capped.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_failed_later_page_fails_listing() {
+        let first = "https://api.github.com/repos/o/r/releases?per_page=100";
+        let transport = MapTransport(HashMap::from([(
+            first,
+            (
+                r#"[{"id":1,"tag_name":"v2","name":null}]"#,
+                Some(r#"<https://api.github.com/repos/o/r/releases?per_page=100&page=2>; rel="next""#),
+            ),
+        )]));
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(transport));
+
+        assert!(matches!(client.get_releases("o", "r"), Err(GitHubError::NotFound)));
+    }
+
+    #[test]
+    fn test_truncated_tree_is_walked() {
+        let transport = MapTransport(HashMap::from([
+            (
+                "https://api.github.com/repos/o/r/git/trees/main?recursive=1",
+                (r#"{"sha":"root","tree":[],"truncated":true}"#, None),
+            ),
+            (
+                "https://api.github.com/repos/o/r/git/trees/root",
+                (
+                    r#"{"sha":"root","tree":[
+                        {"path":"build.gradle.kts","type":"blob","sha":"b1"},
+                        {"path":"core","type":"tree","sha":"t1"}
+                    ]}"#,
+                    None,
+                ),
+            ),
+            (
+                "https://api.github.com/repos/o/r/git/trees/t1",
+                (
+                    r#"{"sha":"t1","tree":[{"path":"build.gradle.kts","type":"blob","sha":"b2"}]}"#,
+                    None,
+                ),
+            ),
+        ]));
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(transport));
+
+        let tree = client.get_tree("o", "r", "main").unwrap();
+        assert!(!tree.truncated);
+        assert_eq!(
+            tree.tree.iter().map(|e| e.path.as_str()).collect::<Vec<_>>(),
+            vec!["build.gradle.kts", "core", "core/build.gradle.kts"]
+        );
+    }
+
+    #[test]
+    fn test_conditional_request_serves_cached_body() {
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(EtagTransport));
+
+        let first = client.get_releases("example", "hello-allay").unwrap();
+        let second = client.get_releases("example", "hello-allay").unwrap();
+
+        assert_eq!(first[0].tag_name, "v1.0.0");
+        assert_eq!(second[0].tag_name, "v1.0.0");
+        assert_eq!(client.api_calls(), 2);
+        assert_eq!(client.cache_hits(), 1);
+        assert_eq!(client.export_data_cache().responses.len(), 1);
+    }
+
+    #[test]
+    fn test_deleted_repository_drops_cached_entries() {
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(DeletedTransport(Mutex::new(HashSet::new()))));
+
+        assert!(client.get_repository("o", "r").is_ok());
+        assert!(client.get_tree("o", "r", "main").is_ok());
+        assert_eq!(client.export_data_cache().repositories.len(), 1);
+        assert_eq!(client.export_data_cache().trees.len(), 1);
+
+        assert_eq!(client.get_repository("o", "r").unwrap_err(), GitHubError::NotFound);
+        assert_eq!(client.get_tree("o", "r", "main").unwrap_err(), GitHubError::NotFound);
+        assert!(client.export_data_cache().repositories.is_empty());
+        assert!(client.export_data_cache().trees.is_empty());
+    }
+
+    #[test]
+    fn test_cached_body_only_covers_transient_errors() {
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(SequenceTransport::new(vec![
+            (200, &[("ETag", "\"v1\"")]),
+            (502, &[]),
+            (401, &[]),
+        ])));
+
+        assert!(client.get_releases("o", "r").unwrap().is_empty());
+        assert!(client.get_releases("o", "r").unwrap().is_empty());
+        assert_eq!(client.get_releases("o", "r").unwrap_err(), GitHubError::Unauthorized);
+        // The transport has run out of responses, which is a network error.
+        assert!(client.get_releases("o", "r").unwrap().is_empty());
+    }
+}
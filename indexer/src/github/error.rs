@@ -0,0 +1,95 @@
+use std::fmt;
+
+/// Why a GitHub request failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitHubError {
+    /// 404: the repository, file or ref doesn't exist (anymore).
+    NotFound,
+    /// 304 on a conditional request: the cached copy is still current.
+    NotModified,
+    /// 403/429 that retries didn't get past, or the run's API call budget is
+    /// spent. `reset` is the unix time the quota refills, when known.
+    RateLimited { reset: Option<u64> },
+    /// 401, or no usable credentials.
+    Unauthorized,
+    /// Any other unexpected status.
+    Status(u16),
+    /// The request didn't complete.
+    Network(String),
+    /// The response, or a file read from it, wasn't what was expected.
+    Parse(String),
+}
+
+impl GitHubError {
+    /// Error for a response with a non-success `status`.
+    pub fn from_status(status: u16, reset: Option<u64>) -> Self {
+        match status {
+            304 => Self::NotModified,
+            401 => Self::Unauthorized,
+            403 | 429 => Self::RateLimited { reset },
+            404 => Self::NotFound,
+            code => Self::Status(code),
+        }
+    }
+
+    /// Whether the request may well succeed later: it didn't complete, or
+    /// GitHub answered with a server error.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Network(_) | Self::Status(500..=599))
+    }
+
+    /// Short stable name for reports.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotFound => "not_found",
+            Self::NotModified => "not_modified",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::Unauthorized => "unauthorized",
+            Self::Status(_) => "status",
+            Self::Network(_) => "network",
+            Self::Parse(_) => "parse",
+        }
+    }
+}
+
+impl fmt::Display for GitHubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "not found"),
+            Self::NotModified => write!(f, "not modified"),
+            Self::RateLimited { reset: Some(reset) } => write!(f, "rate limited until {}", reset),
+            Self::RateLimited { reset: None } => write!(f, "rate limited"),
+            Self::Unauthorized => write!(f, "unauthorized"),
+            Self::Status(code) => write!(f, "HTTP error: status {}", code),
+            Self::Network(e) => write!(f, "{}", e),
+            Self::Parse(e) => write!(f, "Parse error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GitHubError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_status() {
+        assert_eq!(GitHubError::from_status(404, None), GitHubError::NotFound);
+        assert_eq!(GitHubError::from_status(401, None), GitHubError::Unauthorized);
+        assert_eq!(
+            GitHubError::from_status(429, Some(60)),
+            GitHubError::RateLimited { reset: Some(60) }
+        );
+        assert_eq!(GitHubError::from_status(502, None).to_string(), "HTTP error: status 502");
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(GitHubError::Network("timed out".to_string()).is_transient());
+        assert!(GitHubError::from_status(503, None).is_transient());
+        assert!(!GitHubError::from_status(403, None).is_transient());
+        assert!(!GitHubError::from_status(401, None).is_transient());
+        assert!(!GitHubError::from_status(422, None).is_transient());
+    }
+}
@@ -0,0 +1,490 @@
+use super::types::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// README paths tried at the repository root, in order. The REST `readme`
+/// endpoint is still used when none of them exists.
+const README_CANDIDATES: &[&str] = &["README.md", "readme.md", "Readme.md", "README"];
+
+const RELEASES_PER_REPO: usize = 30;
+const ASSETS_PER_RELEASE: usize = 20;
+const TOPICS_PER_REPO: usize = 20;
+
+/// Everything `update` needs from a repository except its files and
+/// contributors, as fetched by one GraphQL query.
+#[derive(Debug, Clone)]
+pub struct RepositorySnapshot {
+    pub repository: Repository,
+    /// OID of the tree at the tip of the default branch, i.e. the `sha` the
+    /// REST tree endpoint would report.
+    pub tree_oid: Option<String>,
+    /// `None` when the repository has more releases, or a release more
+    /// assets, than one query fetches; the paginated REST listing is used then.
+    pub releases: Option<Vec<Release>>,
+    /// `None` when no README was found at one of the candidate paths.
+    pub readme: Option<String>,
+}
+
+/// Build a query fetching every `(owner, name)` pair under the alias `r<index>`.
+pub fn repositories_query(repos: &[(String, String)]) -> String {
+    let readme_fields: String = README_CANDIDATES
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            format!(
+                "readme{}: object(expression: {}) {{ ... on Blob {{ text }} }} ",
+                i,
+                quote(&format!("HEAD:{}", path))
+            )
+        })
+        .collect();
+
+    let mut query = String::from("query {");
+    for (i, (owner, name)) in repos.iter().enumerate() {
+        query.push_str(&format!(
+            " r{}: repository(owner: {}, name: {}) {{ ...repo {} }}",
+            i,
+            quote(owner),
+            quote(name),
+            readme_fields.trim_end()
+        ));
+    }
+    query.push_str(" }");
+    query.push_str(&format!(
+        " fragment repo on Repository {{ \
+databaseId nameWithOwner name description url stargazerCount forkCount \
+createdAt updatedAt pushedAt isTemplate isFork isArchived \
+owner {{ login avatarUrl url }} \
+licenseInfo {{ key name spdxId url }} \
+repositoryTopics(first: {}) {{ nodes {{ topic {{ name }} }} }} \
+defaultBranchRef {{ name target {{ ... on Commit {{ tree {{ oid }} }} }} }} \
+releases(first: {}, orderBy: {{field: CREATED_AT, direction: DESC}}) {{ totalCount nodes {{ \
+databaseId tagName name description isPrerelease isDraft createdAt publishedAt \
+releaseAssets(first: {}) {{ totalCount nodes {{ name size downloadCount downloadUrl contentType }} }} \
+}} }} }}",
+        TOPICS_PER_REPO, RELEASES_PER_REPO, ASSETS_PER_RELEASE
+    ));
+    query
+}
+
+/// Quote a string as a GraphQL literal; JSON string escaping is compatible.
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// Parse the response of [`repositories_query`] for `count` repositories.
+///
+/// Repositories GitHub could not resolve (missing, renamed, no access) come
+/// back as `None` so the caller can fall back to REST for them. A response
+/// without any `data` is an error.
+pub fn parse_repositories_response(
+    body: &str,
+    count: usize,
+) -> Result<Vec<Option<RepositorySnapshot>>, String> {
+    let resp: GraphQlResponse =
+        serde_json::from_str(body).map_err(|e| format!("Parse error: {}", e))?;
+
+    let Some(mut data) = resp.data else {
+        let message = resp
+            .errors
+            .first()
+            .map_or("no data", |e| e.message.as_str())
+            .to_string();
+        return Err(format!("GraphQL error: {}", message));
+    };
+
+    Ok((0..count)
+        .map(|i| {
+            data.remove(&format!("r{}", i))
+                .flatten()
+                .map(RepositoryNode::into_snapshot)
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct GraphQlResponse {
+    #[serde(default)]
+    data: Option<HashMap<String, Option<RepositoryNode>>>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepositoryNode {
+    #[serde(default)]
+    database_id: Option<u64>,
+    name_with_owner: String,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    stargazer_count: u64,
+    #[serde(default)]
+    fork_count: u64,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    updated_at: Option<String>,
+    #[serde(default)]
+    pushed_at: Option<String>,
+    #[serde(default)]
+    is_template: bool,
+    #[serde(default)]
+    is_fork: bool,
+    #[serde(default)]
+    is_archived: bool,
+    owner: OwnerNode,
+    #[serde(default)]
+    license_info: Option<LicenseNode>,
+    #[serde(default)]
+    repository_topics: Connection<TopicNode>,
+    #[serde(default)]
+    default_branch_ref: Option<BranchNode>,
+    #[serde(default)]
+    releases: Connection<ReleaseNode>,
+    #[serde(flatten)]
+    readmes: HashMap<String, Option<BlobNode>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OwnerNode {
+    login: String,
+    #[serde(default)]
+    avatar_url: String,
+    #[serde(default)]
+    url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LicenseNode {
+    #[serde(default)]
+    key: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    spdx_id: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "T: Deserialize<'de>")]
+struct Connection<T> {
+    #[serde(default, rename = "totalCount")]
+    total_count: Option<usize>,
+    #[serde(default)]
+    nodes: Vec<Option<T>>,
+}
+
+impl<T> Default for Connection<T> {
+    fn default() -> Self {
+        Self {
+            total_count: None,
+            nodes: Vec::new(),
+        }
+    }
+}
+
+impl<T> Connection<T> {
+    /// Whether `nodes` holds every node, as far as `totalCount` tells.
+    fn is_complete(&self) -> bool {
+        self.total_count.is_none_or(|total| total <= self.nodes.len())
+    }
+
+    fn into_nodes(self) -> impl Iterator<Item = T> {
+        self.nodes.into_iter().flatten()
+    }
+}
+
+#[derive(Deserialize)]
+struct TopicNode {
+    topic: Topic,
+}
+
+#[derive(Deserialize)]
+struct Topic {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct BranchNode {
+    name: String,
+    #[serde(default)]
+    target: Option<CommitNode>,
+}
+
+#[derive(Deserialize)]
+struct CommitNode {
+    #[serde(default)]
+    tree: Option<TreeNode>,
+}
+
+#[derive(Deserialize)]
+struct TreeNode {
+    oid: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReleaseNode {
+    #[serde(default)]
+    database_id: Option<u64>,
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    is_prerelease: bool,
+    #[serde(default)]
+    is_draft: bool,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    release_assets: Connection<AssetNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetNode {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    download_count: u64,
+    #[serde(default)]
+    download_url: String,
+    #[serde(default)]
+    content_type: String,
+}
+
+#[derive(Deserialize)]
+struct BlobNode {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+impl RepositoryNode {
+    /// Convert into the shapes the REST endpoints return, so the builder
+    /// can't tell which API the data came from.
+    fn into_snapshot(mut self) -> RepositorySnapshot {
+        let readme = (0..README_CANDIDATES.len()).find_map(|i| {
+            self.readmes
+                .remove(&format!("readme{}", i))
+                .flatten()
+                .and_then(|b| b.text)
+        });
+        let tree_oid = self
+            .default_branch_ref
+            .as_ref()
+            .and_then(|b| b.target.as_ref())
+            .and_then(|c| c.tree.as_ref())
+            .map(|t| t.oid.clone());
+
+        let complete = self.releases.is_complete()
+            && self
+                .releases
+                .nodes
+                .iter()
+                .flatten()
+                .all(|r| r.release_assets.is_complete());
+        let releases: Vec<Release> = self
+            .releases
+            .into_nodes()
+            .map(|r| Release {
+                id: r.database_id.unwrap_or_default(),
+                tag_name: r.tag_name,
+                name: r.name,
+                body: r.description,
+                prerelease: r.is_prerelease,
+                draft: r.is_draft,
+                created_at: r.created_at.unwrap_or_default(),
+                published_at: r.published_at.unwrap_or_default(),
+                assets: r
+                    .release_assets
+                    .into_nodes()
+                    .map(|a| ReleaseAsset {
+                        id: 0,
+                        name: a.name,
+                        size: a.size,
+                        download_count: a.download_count,
+                        browser_download_url: a.download_url,
+                        content_type: a.content_type,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let repository = Repository {
+            id: self.database_id.unwrap_or_default(),
+            contributors_url: format!(
+                "https://api.github.com/repos/{}/contributors",
+                self.name_with_owner
+            ),
+            full_name: self.name_with_owner,
+            name: self.name,
+            description: self.description,
+            html_url: self.url,
+            stargazers_count: self.stargazer_count,
+            forks_count: self.fork_count,
+            created_at: self.created_at.unwrap_or_default(),
+            updated_at: self.updated_at.unwrap_or_default(),
+            pushed_at: self.pushed_at.unwrap_or_default(),
+            owner: Owner {
+                login: self.owner.login,
+                avatar_url: self.owner.avatar_url,
+                html_url: self.owner.url,
+            },
+            license: self.license_info.map(|l| RepositoryLicense {
+                key: l.key,
+                name: l.name,
+                // REST reports unrecognised licenses as NOASSERTION.
+                spdx_id: l.spdx_id.unwrap_or_else(|| "NOASSERTION".to_string()),
+                url: l.url,
+                html_url: None,
+            }),
+            topics: self
+                .repository_topics
+                .into_nodes()
+                .map(|t| t.topic.name)
+                .collect(),
+            is_template: self.is_template,
+            fork: self.is_fork,
+            archived: self.is_archived,
+            default_branch: self.default_branch_ref.map(|b| b.name),
+        };
+
+        RepositorySnapshot {
+            repository,
+            tree_oid,
+            releases: complete.then_some(releases),
+            readme,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repositories_query_aliases_and_escapes() {
+        let query = repositories_query(&[
+            ("example".to_string(), "hello-allay".to_string()),
+            ("odd\"owner".to_string(), "repo".to_string()),
+        ]);
+        assert!(query.contains(r#"r0: repository(owner: "example", name: "hello-allay")"#));
+        assert!(query.contains(r#"r1: repository(owner: "odd\"owner", name: "repo")"#));
+        assert!(query.contains(r#"readme0: object(expression: "HEAD:README.md")"#));
+        assert_eq!(query.matches("fragment repo on Repository").count(), 1);
+    }
+
+    #[test]
+    fn test_parse_repositories_response() {
+        let body = r##"{
+            "data": {
+                "r0": {
+                    "databaseId": 7,
+                    "nameWithOwner": "example/hello-allay",
+                    "name": "hello-allay",
+                    "description": "Greets players",
+                    "url": "https://github.com/example/hello-allay",
+                    "stargazerCount": 12,
+                    "forkCount": 1,
+                    "createdAt": "2024-01-01T00:00:00Z",
+                    "updatedAt": "2024-02-01T00:00:00Z",
+                    "pushedAt": "2024-02-01T00:00:00Z",
+                    "isTemplate": false,
+                    "isFork": false,
+                    "isArchived": false,
+                    "owner": { "login": "example", "avatarUrl": "https://a/e.png", "url": "https://github.com/example" },
+                    "licenseInfo": { "key": "other", "name": "Other", "spdxId": null, "url": null },
+                    "repositoryTopics": { "nodes": [{ "topic": { "name": "social" } }] },
+                    "defaultBranchRef": { "name": "main", "target": { "tree": { "oid": "abc123" } } },
+                    "releases": { "totalCount": 1, "nodes": [{
+                        "databaseId": 3,
+                        "tagName": "v1.2.0",
+                        "name": null,
+                        "description": "Changes",
+                        "isPrerelease": false,
+                        "isDraft": false,
+                        "createdAt": "2024-02-01T00:00:00Z",
+                        "publishedAt": "2024-02-01T00:00:00Z",
+                        "releaseAssets": { "totalCount": 1, "nodes": [{
+                            "name": "HelloAllay.jar",
+                            "size": 100,
+                            "downloadCount": 40,
+                            "downloadUrl": "https://github.com/example/hello-allay/releases/download/v1.2.0/HelloAllay.jar",
+                            "contentType": "application/java-archive"
+                        }] }
+                    }] },
+                    "readme0": null,
+                    "readme1": { "text": "# Hello" },
+                    "readme2": null,
+                    "readme3": null
+                },
+                "r1": null
+            },
+            "errors": [{ "type": "NOT_FOUND", "message": "Could not resolve to a Repository" }]
+        }"##;
+
+        let snapshots = parse_repositories_response(body, 2).unwrap();
+        assert!(snapshots[1].is_none());
+
+        let snapshot = snapshots[0].as_ref().unwrap();
+        let repo = &snapshot.repository;
+        assert_eq!(repo.full_name, "example/hello-allay");
+        assert_eq!(repo.stargazers_count, 12);
+        assert_eq!(repo.topics, vec!["social"]);
+        assert_eq!(repo.default_branch.as_deref(), Some("main"));
+        assert_eq!(repo.license.as_ref().unwrap().spdx_id, "NOASSERTION");
+        assert_eq!(
+            repo.contributors_url,
+            "https://api.github.com/repos/example/hello-allay/contributors"
+        );
+        assert_eq!(snapshot.tree_oid.as_deref(), Some("abc123"));
+        assert_eq!(snapshot.readme.as_deref(), Some("# Hello"));
+        let releases = snapshot.releases.as_ref().unwrap();
+        assert_eq!(releases[0].tag_name, "v1.2.0");
+        assert_eq!(releases[0].assets[0].download_count, 40);
+    }
+
+    #[test]
+    fn test_release_with_more_assets_is_incomplete() {
+        let body = r#"{"data":{"r0":{
+            "nameWithOwner": "example/hello-allay",
+            "name": "hello-allay",
+            "owner": { "login": "example" },
+            "releases": { "totalCount": 1, "nodes": [{
+                "tagName": "v1.0.0",
+                "releaseAssets": { "totalCount": 21, "nodes": [{ "name": "HelloAllay.jar" }] }
+            }] }
+        }}}"#;
+
+        let snapshots = parse_repositories_response(body, 1).unwrap();
+        assert!(snapshots[0].as_ref().unwrap().releases.is_none());
+    }
+
+    #[test]
+    fn test_parse_repositories_response_without_data() {
+        let body = r#"{"errors":[{"message":"Bad credentials"}]}"#;
+        assert_eq!(
+            parse_repositories_response(body, 1).unwrap_err(),
+            "GraphQL error: Bad credentials"
+        );
+    }
+}
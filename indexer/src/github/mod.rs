@@ -1,16 +1,26 @@
 pub mod api;
 pub mod auth;
+pub mod error;
+pub mod graphql;
+mod pool;
+pub mod transport;
 pub mod types;
 
-pub use api::{BatchResult, GitHubClient};
+pub use api::{BatchResult, DEFAULT_CONCURRENCY, DEFAULT_MAX_PAGES, GitHubClient};
 pub use auth::GitHubAppAuth;
+pub use error::GitHubError;
+pub use graphql::RepositorySnapshot;
+pub use transport::{
+    FixtureTransport, RecordingTransport, Transport, TransportResponse, UreqTransport,
+};
 pub use types::*;
 
 use crate::cache::DataCache;
 use crate::util::get_arg;
 use std::env;
 use std::fs;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
+use tracing::info;
 
 static CLIENT: OnceLock<GitHubClient> = OnceLock::new();
 
@@ -21,18 +31,74 @@ pub fn init_client(args: &[String]) -> Result<(), String> {
         .map_err(|_| "Client already initialized".to_string())
 }
 
+/// Install an already configured client, e.g. one backed by a `FixtureTransport`.
+pub fn init_client_with(client: GitHubClient) -> Result<(), String> {
+    CLIENT
+        .set(client)
+        .map_err(|_| "Client already initialized".to_string())
+}
+
 pub fn client() -> &'static GitHubClient {
     CLIENT.get().expect("GitHub client not initialized")
 }
 
 fn create_client(args: &[String]) -> Result<GitHubClient, String> {
+    let mut client = create_transport_client(args)?;
+    if let Some(pages) = get_arg(args, "--max-pages") {
+        let pages = pages
+            .parse()
+            .map_err(|_| "--max-pages expects a number".to_string())?;
+        client.set_max_pages(pages);
+    }
+    if let Some(concurrency) = get_arg(args, "--concurrency") {
+        let concurrency = concurrency
+            .parse()
+            .map_err(|_| "--concurrency expects a number".to_string())?;
+        client.set_concurrency(concurrency);
+    }
+    if let Some(max) = get_arg(args, "--max-api-calls") {
+        let max = max
+            .parse()
+            .map_err(|_| "--max-api-calls expects a number".to_string())?;
+        client.set_max_api_calls(Some(max));
+    }
+    client.set_wait_for_reset(args.iter().any(|a| a == "--wait-for-reset"));
+    Ok(client)
+}
+
+fn create_transport_client(args: &[String]) -> Result<GitHubClient, String> {
+    if let Some(dir) = get_arg(args, "--replay") {
+        info!(dir = %dir, "Replaying recorded responses");
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(FixtureTransport::new(dir)));
+        return Ok(client);
+    }
+
+    let mut client = create_authenticated_client(args)?;
+    if let Some(dir) = get_arg(args, "--record") {
+        info!(dir = %dir, "Recording responses");
+        client.set_transport(Arc::new(RecordingTransport::new(
+            Arc::new(UreqTransport),
+            dir,
+        )));
+    }
+    Ok(client)
+}
+
+fn create_authenticated_client(args: &[String]) -> Result<GitHubClient, String> {
     let app_id = get_arg(args, "--app-id").or_else(|| env::var("GITHUB_APP_ID").ok());
     let installation_id =
         get_arg(args, "--installation-id").or_else(|| env::var("GITHUB_INSTALLATION_ID").ok());
     let private_key_file = get_arg(args, "--private-key-file");
     let private_key_env = env::var("GITHUB_PRIVATE_KEY").ok();
 
-    let data_cache = DataCache::load();
+    // A recording must hold every response a replay will ask for, so
+    // nothing may be answered from the cache, blobs and revisions included.
+    let data_cache = if get_arg(args, "--record").is_some() {
+        DataCache::default()
+    } else {
+        DataCache::load()
+    };
 
     if let (Some(app_id), Some(installation_id)) = (app_id, installation_id) {
         let private_key = if let Some(path) = private_key_file {
@@ -43,7 +109,7 @@ fn create_client(args: &[String]) -> Result<GitHubClient, String> {
             return Err("GitHub App requires --private-key-file or GITHUB_PRIVATE_KEY".to_string());
         };
 
-        println!("Using GitHub App authentication (15,000 req/hour)");
+        info!("Using GitHub App authentication (15,000 req/hour)");
         return Ok(GitHubClient::with_app_and_cache(
             GitHubAppAuth {
                 app_id,
@@ -56,7 +122,7 @@ fn create_client(args: &[String]) -> Result<GitHubClient, String> {
 
     let token = get_arg(args, "--token").or_else(|| env::var("GITHUB_TOKEN").ok());
     if let Some(t) = token {
-        println!("Using personal token authentication (5,000 req/hour)");
+        info!("Using personal token authentication (5,000 req/hour)");
         return Ok(GitHubClient::new_with_cache(Some(t), data_cache));
     }
 
@@ -0,0 +1,72 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Paces work to a steady rate while allowing short bursts: each item takes a
+/// token, and tokens refill continuously up to `capacity`.
+pub(crate) struct TokenBucket {
+    capacity: f64,
+    per_sec: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    /// A full bucket of `capacity` tokens refilled at `per_sec`.
+    pub fn new(capacity: usize, per_sec: f64) -> Self {
+        let capacity = capacity.max(1) as f64;
+        Self {
+            capacity,
+            per_sec,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Block until a token is available and take it.
+    pub fn acquire(&self) {
+        while let Some(wait) = self.try_acquire(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+
+    /// Take a token, or return how long until one is available.
+    fn try_acquire(&self, now: Instant) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        let (tokens, last) = *state;
+        let elapsed = now.saturating_duration_since(last).as_secs_f64();
+        let tokens = (tokens + elapsed * self.per_sec).min(self.capacity);
+
+        if tokens >= 1.0 {
+            *state = (tokens - 1.0, now);
+            return None;
+        }
+        *state = (tokens, now);
+        if self.per_sec <= 0.0 {
+            return Some(Duration::from_secs(1));
+        }
+        Some(Duration::from_secs_f64((1.0 - tokens) / self.per_sec))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bursts_then_paces() {
+        let bucket = TokenBucket::new(2, 4.0);
+        let start = *bucket.state.lock().unwrap();
+        let now = start.1;
+
+        assert_eq!(bucket.try_acquire(now), None);
+        assert_eq!(bucket.try_acquire(now), None);
+        assert_eq!(bucket.try_acquire(now), Some(Duration::from_millis(250)));
+
+        let later = now + Duration::from_millis(250);
+        assert_eq!(bucket.try_acquire(later), None);
+        // Idle time refills no further than the capacity.
+        let idle = later + Duration::from_secs(60);
+        assert_eq!(bucket.try_acquire(idle), None);
+        assert_eq!(bucket.try_acquire(idle), None);
+        assert!(bucket.try_acquire(idle).is_some());
+    }
+}
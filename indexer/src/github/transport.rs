@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+/// A response as seen by `GitHubClient`, independent of the HTTP backend.
+/// Header names are stored lowercased.
+#[derive(Debug, Clone, Default)]
+pub struct TransportResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl TransportResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+/// Performs requests on behalf of `GitHubClient`: GETs against the REST API
+/// and POSTs of GraphQL queries.
+///
+/// Non-2xx statuses are returned as responses, not errors; `Err` is reserved
+/// for failures where no response was received at all.
+pub trait Transport: Send + Sync {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<TransportResponse, String>;
+
+    fn post(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Result<TransportResponse, String>;
+}
+
+/// Talks to the real GitHub API.
+pub struct UreqTransport;
+
+impl Transport for UreqTransport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<TransportResponse, String> {
+        let mut req = ureq::get(url).config().http_status_as_error(false).build();
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+
+        let resp = req.call().map_err(|e| format!("HTTP error: {}", e))?;
+        into_transport_response(resp)
+    }
+
+    fn post(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Result<TransportResponse, String> {
+        let mut req = ureq::post(url).config().http_status_as_error(false).build();
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+
+        let resp = req.send(body).map_err(|e| format!("HTTP error: {}", e))?;
+        into_transport_response(resp)
+    }
+}
+
+fn into_transport_response(
+    mut resp: ureq::http::Response<ureq::Body>,
+) -> Result<TransportResponse, String> {
+    let headers = resp
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|v| (name.as_str().to_ascii_lowercase(), v.to_string()))
+        })
+        .collect();
+    let status = resp.status().as_u16();
+    let body = resp
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| format!("Read error: {}", e))?;
+
+    Ok(TransportResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Serves responses from a directory of recorded files instead of the network.
+///
+/// Each URL maps to a path under the directory (see [`fixture_key`]). JSON
+/// responses live at `<key>.json`, raw file contents at `<key>` itself, so
+/// `repos/o/r/contents/build.gradle.kts` holds the build script verbatim.
+/// An optional `<file>.meta.json` sidecar overrides the status and headers.
+/// URLs without a fixture answer `404`.
+pub struct FixtureTransport {
+    dir: PathBuf,
+}
+
+/// Sidecar describing a recorded response. Only `status` and `headers` are
+/// used on replay; `url` and `request_headers` are kept for humans reading a
+/// bundle attached to an issue.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct FixtureMeta {
+    pub url: String,
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub request_headers: HashMap<String, String>,
+}
+
+impl Default for FixtureMeta {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            status: 200,
+            headers: HashMap::new(),
+            request_headers: HashMap::new(),
+        }
+    }
+}
+
+impl FixtureTransport {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn find_body_file(&self, key: &str, raw: bool) -> Option<PathBuf> {
+        let json = self.dir.join(format!("{}.json", key));
+        let plain = self.dir.join(key);
+        let candidates = if raw { [plain, json] } else { [json, plain] };
+        candidates.into_iter().find(|p| p.is_file())
+    }
+}
+
+fn is_raw_request(headers: &[(&str, &str)]) -> bool {
+    headers
+        .iter()
+        .any(|(name, value)| name.eq_ignore_ascii_case("accept") && value.contains("raw"))
+}
+
+impl Transport for FixtureTransport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<TransportResponse, String> {
+        self.serve(&fixture_key(url), is_raw_request(headers))
+    }
+
+    fn post(
+        &self,
+        url: &str,
+        _headers: &[(&str, &str)],
+        body: &str,
+    ) -> Result<TransportResponse, String> {
+        self.serve(&fixture_post_key(url, body), false)
+    }
+}
+
+impl FixtureTransport {
+    fn serve(&self, key: &str, raw: bool) -> Result<TransportResponse, String> {
+        let Some(path) = self.find_body_file(key, raw) else {
+            return Ok(TransportResponse {
+                status: 404,
+                headers: HashMap::new(),
+                body: r#"{"message":"Not Found"}"#.to_string(),
+            });
+        };
+
+        let body = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read fixture {:?}: {}", path, e))?;
+        let meta = read_meta(&path)?;
+
+        Ok(TransportResponse {
+            status: meta.status,
+            headers: meta
+                .headers
+                .into_iter()
+                .map(|(k, v)| (k.to_ascii_lowercase(), v))
+                .collect(),
+            body,
+        })
+    }
+}
+
+pub fn meta_path(body_path: &Path) -> PathBuf {
+    let mut name = body_path.as_os_str().to_owned();
+    name.push(".meta.json");
+    PathBuf::from(name)
+}
+
+fn read_meta(body_path: &Path) -> Result<FixtureMeta, String> {
+    let path = meta_path(body_path);
+    if !path.is_file() {
+        return Ok(FixtureMeta::default());
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read fixture {:?}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid fixture meta {:?}: {}", path, e))
+}
+
+/// Passes requests through to another transport and writes every exchange to
+/// a directory in the layout `FixtureTransport` reads, so the bundle can be
+/// replayed offline.
+///
+/// `If-None-Match` and `If-Modified-Since` are dropped so each response
+/// carries a full body; a `304` would be useless without the recorder's
+/// local cache.
+pub struct RecordingTransport {
+    inner: Arc<dyn Transport>,
+    dir: PathBuf,
+}
+
+impl RecordingTransport {
+    pub fn new(inner: Arc<dyn Transport>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+
+    fn write(
+        &self,
+        url: &str,
+        key: &str,
+        raw: bool,
+        request_headers: &[(&str, &str)],
+        resp: &TransportResponse,
+    ) {
+        let path = if raw {
+            self.dir.join(key)
+        } else {
+            self.dir.join(format!("{}.json", key))
+        };
+
+        let meta = FixtureMeta {
+            url: url.to_string(),
+            status: resp.status,
+            headers: resp.headers.clone(),
+            request_headers: request_headers
+                .iter()
+                .filter(|(name, _)| !name.eq_ignore_ascii_case("authorization"))
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, &resp.body))
+            .and_then(|_| {
+                let json = serde_json::to_string_pretty(&meta).unwrap_or_default();
+                fs::write(meta_path(&path), json)
+            });
+        if let Err(e) = result {
+            warn!(path = ?path, error = %e, "Failed to record response");
+        }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<TransportResponse, String> {
+        let headers: Vec<(&str, &str)> = headers
+            .iter()
+            .filter(|(name, _)| {
+                !name.eq_ignore_ascii_case("if-none-match")
+                    && !name.eq_ignore_ascii_case("if-modified-since")
+            })
+            .copied()
+            .collect();
+
+        let resp = self.inner.get(url, &headers)?;
+        self.write(
+            url,
+            &fixture_key(url),
+            is_raw_request(&headers),
+            &headers,
+            &resp,
+        );
+        Ok(resp)
+    }
+
+    fn post(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Result<TransportResponse, String> {
+        let resp = self.inner.post(url, headers, body)?;
+        self.write(url, &fixture_post_key(url, body), false, headers, &resp);
+        Ok(resp)
+    }
+}
+
+/// Map a request URL to a relative fixture path.
+///
+/// `https://api.github.com/repos/o/r/git/trees/main?recursive=1` becomes
+/// `repos/o/r/git/trees/main@recursive=1`. Hosts other than the API host are
+/// kept as the first path segment. Characters that are awkward in file names
+/// are replaced with `_`, and overly long segments (search queries) are
+/// shortened with a hash suffix.
+pub fn fixture_key(url: &str) -> String {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    let rest = rest.strip_prefix("api.github.com/").unwrap_or(rest);
+
+    let (path, query) = match rest.split_once('?') {
+        Some((p, q)) => (p, Some(q)),
+        None => (rest, None),
+    };
+
+    let mut key: String = path
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != "." && *s != "..")
+        .map(sanitize)
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if let Some(q) = query {
+        let last_start = key.rfind('/').map_or(0, |i| i + 1);
+        let last = format!("{}@{}", &key[last_start..], sanitize(q));
+        key.truncate(last_start);
+        key.push_str(&shorten(last));
+    }
+
+    key
+}
+
+/// Fixture path of a POST, e.g. `graphql@1f0c...` for a GraphQL query. The
+/// body is hashed so each distinct query gets its own file.
+pub fn fixture_post_key(url: &str, body: &str) -> String {
+    format!("{}@{:016x}", fixture_key(url), fnv1a(body))
+}
+
+const MAX_SEGMENT_LEN: usize = 120;
+
+fn shorten(segment: String) -> String {
+    if segment.len() <= MAX_SEGMENT_LEN {
+        return segment;
+    }
+    format!("{}-{:016x}", &segment[..80], fnv1a(&segment))
+}
+
+/// FNV-1a, stable across runs and platforms.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' | '=' | '&' | '%' | '+' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticTransport;
+
+    impl Transport for StaticTransport {
+        fn get(&self, _url: &str, headers: &[(&str, &str)]) -> Result<TransportResponse, String> {
+            assert!(!headers.iter().any(|(n, _)| *n == "If-None-Match"));
+            Ok(TransportResponse {
+                status: 200,
+                headers: HashMap::from([("etag".to_string(), "\"abc\"".to_string())]),
+                body: r#"{"ok":true}"#.to_string(),
+            })
+        }
+
+        fn post(
+            &self,
+            url: &str,
+            headers: &[(&str, &str)],
+            _body: &str,
+        ) -> Result<TransportResponse, String> {
+            self.get(url, headers)
+        }
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let dir = std::env::temp_dir().join(format!("allayindexer-record-{}", std::process::id()));
+        let url = "https://api.github.com/repos/o/r";
+        let headers = [
+            ("Accept", "application/vnd.github+json"),
+            ("Authorization", "Bearer secret"),
+            ("If-None-Match", "\"old\""),
+        ];
+
+        let recorder = RecordingTransport::new(Arc::new(StaticTransport), &dir);
+        recorder.get(url, &headers).unwrap();
+
+        let meta = fs::read_to_string(dir.join("repos/o/r.json.meta.json")).unwrap();
+        assert!(!meta.contains("secret"));
+
+        let replayed = FixtureTransport::new(&dir).get(url, &headers).unwrap();
+        assert_eq!(replayed.status, 200);
+        assert_eq!(replayed.body, r#"{"ok":true}"#);
+        assert_eq!(replayed.header("ETag"), Some("\"abc\""));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_record_then_replay_post() {
+        let dir =
+            std::env::temp_dir().join(format!("allayindexer-record-post-{}", std::process::id()));
+        let url = "https://api.github.com/graphql";
+        let headers = [("Authorization", "Bearer secret")];
+
+        let recorder = RecordingTransport::new(Arc::new(StaticTransport), &dir);
+        recorder.post(url, &headers, r#"{"query":"a"}"#).unwrap();
+
+        let replay = FixtureTransport::new(&dir);
+        let replayed = replay.post(url, &headers, r#"{"query":"a"}"#).unwrap();
+        assert_eq!(replayed.body, r#"{"ok":true}"#);
+        let other = replay.post(url, &headers, r#"{"query":"b"}"#).unwrap();
+        assert_eq!(other.status, 404);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fixture_key_strips_api_host() {
+        assert_eq!(
+            fixture_key("https://api.github.com/repos/AllayMC/Allay"),
+            "repos/AllayMC/Allay"
+        );
+    }
+
+    #[test]
+    fn test_fixture_key_keeps_query() {
+        assert_eq!(
+            fixture_key("https://api.github.com/repos/o/r/git/trees/main?recursive=1"),
+            "repos/o/r/git/trees/main@recursive=1"
+        );
+    }
+
+    #[test]
+    fn test_fixture_key_other_host() {
+        assert_eq!(fixture_key("https://example.com/a/b"), "example.com/a/b");
+    }
+
+    #[test]
+    fn test_fixture_key_shortens_long_queries() {
+        let query = "org.allaymc".repeat(30);
+        let key = fixture_key(&format!(
+            "https://api.github.com/search/code?q={}&page=1",
+            query
+        ));
+        assert!(key.starts_with("search/code@q=org.allaymc"));
+        assert!(key.len() < 120);
+        assert_ne!(
+            key,
+            fixture_key(&format!(
+                "https://api.github.com/search/code?q={}&page=2",
+                query
+            ))
+        );
+    }
+
+    #[test]
+    fn test_fixture_key_rejects_parent_segments() {
+        assert_eq!(
+            fixture_key("https://api.github.com/repos/../../etc/passwd"),
+            "repos/etc/passwd"
+        );
+    }
+}
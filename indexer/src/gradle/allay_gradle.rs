@@ -0,0 +1,129 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+use super::properties::property_reference;
+use super::types::{AllayDsl, AllayGradlePlugin};
+
+/// Plugin id of AllayGradle, which generates `plugin.json` from the
+/// `allay {}` block.
+pub const ALLAY_GRADLE_PLUGIN_ID: &str = "org.allaymc.gradle.plugin";
+
+/// `id("org.allaymc.gradle.plugin")`, optionally followed by `version "x"`,
+/// `.version("x")` or `version someVariable`.
+static PLUGIN_ID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        concat!(
+            r#"\bid\s*\(?\s*["']{}["']\s*\)?"#,
+            r#"(?:[ \t]*\.?[ \t]*version[ \t]*\(?[ \t]*"#,
+            r#"(?:"(?P<dq>[^"]*)"|'(?P<sq>[^']*)'"#,
+            r#"|(?P<expr>[\w.]+(?:\(\s*["'][\w.-]+["']\s*\))?)))?"#,
+        ),
+        regex::escape(ALLAY_GRADLE_PLUGIN_ID)
+    ))
+    .unwrap()
+});
+
+/// `apply plugin: 'org.allaymc.gradle.plugin'` and
+/// `apply(plugin = "org.allaymc.gradle.plugin")`.
+static APPLY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r#"\bapply\s*\(?\s*plugin\s*[:=]\s*["']{}["']"#,
+        regex::escape(ALLAY_GRADLE_PLUGIN_ID)
+    ))
+    .unwrap()
+});
+
+/// `alias(libs.plugins.x)`.
+static ALIAS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\balias\s*\(\s*(?P<accessor>[\w.]+)\s*\)"#).unwrap());
+
+/// Look for AllayGradle in the text of a `plugins {}` block or an `apply`
+/// call. Versions are kept as templates for `apply_properties`. Catalog
+/// aliases go to `catalog_refs` for `apply_catalogs`; until a catalog says
+/// otherwise, one that mentions `allay` is taken to be AllayGradle.
+pub(crate) fn detect_allay_gradle(text: &str, dsl: &mut AllayDsl) {
+    if let Some(caps) = PLUGIN_ID_RE.captures(text) {
+        let version = caps
+            .name("dq")
+            .or_else(|| caps.name("sq"))
+            .map(|m| m.as_str().to_string())
+            .or_else(|| {
+                let expr = caps.name("expr")?.as_str();
+                Some(format!("${{{}}}", property_reference(expr).unwrap_or(expr)))
+            });
+        dsl.allay_gradle = Some(AllayGradlePlugin {
+            version,
+            alias: None,
+        });
+    } else if APPLY_RE.is_match(text) && dsl.allay_gradle.is_none() {
+        dsl.allay_gradle = Some(AllayGradlePlugin::default());
+    }
+
+    for caps in ALIAS_RE.captures_iter(text) {
+        let accessor = caps["accessor"].to_string();
+        if dsl.allay_gradle.is_none() && accessor.to_lowercase().contains("allay") {
+            dsl.allay_gradle = Some(AllayGradlePlugin {
+                version: None,
+                alias: Some(accessor.clone()),
+            });
+        }
+        dsl.catalog_refs.push(accessor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(text: &str) -> AllayDsl {
+        let mut dsl = AllayDsl::default();
+        detect_allay_gradle(text, &mut dsl);
+        dsl
+    }
+
+    fn version(text: &str) -> Option<String> {
+        detect(text).allay_gradle.and_then(|g| g.version)
+    }
+
+    #[test]
+    fn test_plugin_versions() {
+        assert_eq!(
+            version(r#"id("org.allaymc.gradle.plugin") version "0.2.1""#).as_deref(),
+            Some("0.2.1")
+        );
+        assert_eq!(
+            version(r#"id("org.allaymc.gradle.plugin").version("0.2.1")"#).as_deref(),
+            Some("0.2.1")
+        );
+        assert_eq!(
+            version("id 'org.allaymc.gradle.plugin' version '0.2.1'").as_deref(),
+            Some("0.2.1")
+        );
+        assert_eq!(
+            version(r#"id("org.allaymc.gradle.plugin") version allayGradleVersion apply false"#)
+                .as_deref(),
+            Some("${allayGradleVersion}")
+        );
+        assert_eq!(
+            version(r#"id("org.allaymc.gradle.plugin") version property("agp") as String"#)
+                .as_deref(),
+            Some("${agp}")
+        );
+        assert_eq!(version("id(\"org.allaymc.gradle.plugin\")\nid(\"java\")"), None);
+        assert!(detect(r#"id("org.allaymc.gradle.plugin")"#).allay_gradle.is_some());
+        assert!(detect(r#"id("com.gradleup.shadow") version "8.3.0""#).allay_gradle.is_none());
+    }
+
+    #[test]
+    fn test_apply_and_alias() {
+        assert!(detect("apply plugin: 'org.allaymc.gradle.plugin'").allay_gradle.is_some());
+        assert!(detect(r#"apply(plugin = "org.allaymc.gradle.plugin")"#).allay_gradle.is_some());
+
+        let dsl = detect("alias(libs.plugins.allaygradle)\nalias(libs.plugins.shadow)");
+        assert_eq!(
+            dsl.allay_gradle.unwrap().alias.as_deref(),
+            Some("libs.plugins.allaygradle")
+        );
+        assert_eq!(dsl.catalog_refs, ["libs.plugins.allaygradle", "libs.plugins.shadow"]);
+    }
+}
@@ -0,0 +1,493 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use super::allay_gradle::ALLAY_GRADLE_PLUGIN_ID;
+use super::types::{AllayDsl, AllayGradlePlugin, VersionRef};
+
+const ALLAY_GROUP: &str = "org.allaymc.allay";
+
+/// The catalog Gradle creates from `gradle/libs.versions.toml`.
+pub const DEFAULT_CATALOG: &str = "libs";
+
+/// `create("deps") { from(files("...")) }` in Kotlin settings, or
+/// `deps { from(files('...')) }` in Groovy ones.
+static SETTINGS_CATALOG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r#"(?:create\(\s*["'](?P<created>\w+)["']\s*\)|\b(?P<named>\w+))"#,
+        r#"\s*\{\s*from\s*\(?\s*files\(\s*["'](?P<path>[^"']+)["']"#,
+    ))
+    .unwrap()
+});
+
+static ACCESSOR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z_]\w*(?:\s*\.\s*[A-Za-z_]\w*)+").unwrap());
+
+/// Trailing calls on an accessor that don't change what it refers to.
+const ACCESSOR_SUFFIXES: &[&str] = &["get", "asProvider", "orNull", "toString"];
+
+/// A `[libraries]` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogLibrary {
+    pub group: String,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// A `[plugins]` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogPlugin {
+    pub id: String,
+    pub version: Option<String>,
+}
+
+/// One `*.versions.toml` file, keyed by normalized alias: `allay-api`,
+/// `allay_api` and `allay.api` all become `allay.api`, the accessor path
+/// Gradle generates for them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VersionCatalog {
+    versions: HashMap<String, String>,
+    libraries: HashMap<String, CatalogLibrary>,
+    bundles: HashMap<String, Vec<String>>,
+    plugins: HashMap<String, CatalogPlugin>,
+}
+
+impl VersionCatalog {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let toml: toml::Table = content.parse().map_err(|e| format!("{}", e))?;
+        let mut catalog = Self::default();
+
+        if let Some(versions) = toml.get("versions").and_then(|v| v.as_table()) {
+            for (alias, value) in versions {
+                if let Some(v) = rich_version(value) {
+                    catalog.versions.insert(normalize_alias(alias), v);
+                }
+            }
+        }
+
+        if let Some(libraries) = toml.get("libraries").and_then(|v| v.as_table()) {
+            for (alias, value) in libraries {
+                if let Some(library) = catalog.parse_library(value) {
+                    catalog.libraries.insert(normalize_alias(alias), library);
+                }
+            }
+        }
+
+        if let Some(bundles) = toml.get("bundles").and_then(|v| v.as_table()) {
+            for (alias, value) in bundles {
+                let members = value
+                    .as_array()
+                    .map(|a| a.iter().filter_map(|m| m.as_str()).map(normalize_alias).collect())
+                    .unwrap_or_default();
+                catalog.bundles.insert(normalize_alias(alias), members);
+            }
+        }
+
+        if let Some(plugins) = toml.get("plugins").and_then(|v| v.as_table()) {
+            for (alias, value) in plugins {
+                if let Some(plugin) = catalog.parse_plugin(value) {
+                    catalog.plugins.insert(normalize_alias(alias), plugin);
+                }
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    /// `"group:name:version"`, or a table with `module` or `group`/`name`
+    /// and an optional `version`.
+    fn parse_library(&self, value: &toml::Value) -> Option<CatalogLibrary> {
+        if let Some(notation) = value.as_str() {
+            let mut parts = notation.splitn(3, ':');
+            return Some(CatalogLibrary {
+                group: parts.next()?.to_string(),
+                name: parts.next()?.to_string(),
+                version: parts.next().map(str::to_string),
+            });
+        }
+
+        let table = value.as_table()?;
+        let (group, name) = match table.get("module").and_then(|m| m.as_str()) {
+            Some(module) => {
+                let (group, name) = module.split_once(':')?;
+                (group.to_string(), name.to_string())
+            }
+            None => (
+                table.get("group")?.as_str()?.to_string(),
+                table.get("name")?.as_str()?.to_string(),
+            ),
+        };
+        Some(CatalogLibrary {
+            group,
+            name,
+            version: self.entry_version(table),
+        })
+    }
+
+    /// `"id:version"`, or a table with `id` and an optional `version`.
+    fn parse_plugin(&self, value: &toml::Value) -> Option<CatalogPlugin> {
+        if let Some(notation) = value.as_str() {
+            let (id, version) = match notation.split_once(':') {
+                Some((id, version)) => (id, Some(version.to_string())),
+                None => (notation, None),
+            };
+            return Some(CatalogPlugin {
+                id: id.to_string(),
+                version,
+            });
+        }
+
+        let table = value.as_table()?;
+        Some(CatalogPlugin {
+            id: table.get("id")?.as_str()?.to_string(),
+            version: self.entry_version(table),
+        })
+    }
+
+    /// The `version` of a library or plugin table: a literal, a rich
+    /// version, or a `ref` to `[versions]`, also written `version.ref`.
+    fn entry_version(&self, table: &toml::Table) -> Option<String> {
+        let version = table.get("version")?;
+        if let Some(reference) = version.get("ref").and_then(|r| r.as_str()) {
+            return self.version(reference).map(str::to_string);
+        }
+        rich_version(version)
+    }
+
+    /// A `[versions]` entry, which `libs.versions.x` reads.
+    pub fn version(&self, alias: &str) -> Option<&str> {
+        self.versions.get(&normalize_alias(alias)).map(String::as_str)
+    }
+
+    pub fn library(&self, alias: &str) -> Option<&CatalogLibrary> {
+        self.libraries.get(&normalize_alias(alias))
+    }
+
+    pub fn plugin(&self, alias: &str) -> Option<&CatalogPlugin> {
+        self.plugins.get(&normalize_alias(alias))
+    }
+
+    /// The libraries an accessor path below the catalog name refers to:
+    /// `allay.api`, or every member of `bundles.allay`.
+    pub fn libraries_at(&self, path: &str) -> Vec<&CatalogLibrary> {
+        let path = normalize_alias(path);
+        if let Some(bundle) = path.strip_prefix("bundles.") {
+            return self
+                .bundles
+                .get(bundle)
+                .into_iter()
+                .flatten()
+                .filter_map(|alias| self.libraries.get(alias))
+                .collect();
+        }
+        self.libraries.get(&path).into_iter().collect()
+    }
+
+    /// Normalized accessor paths below the catalog name that lead to an
+    /// Allay library or AllayGradle.
+    fn allay_paths(&self) -> impl Iterator<Item = String> + '_ {
+        let libraries = self
+            .libraries
+            .iter()
+            .filter(|(_, lib)| lib.group == ALLAY_GROUP)
+            .map(|(alias, _)| alias.clone());
+        let bundles = self
+            .bundles
+            .iter()
+            .filter(|(_, members)| {
+                members
+                    .iter()
+                    .any(|m| self.libraries.get(m).is_some_and(|l| l.group == ALLAY_GROUP))
+            })
+            .map(|(alias, _)| format!("bundles.{}", alias));
+        let plugins = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin)| plugin.id == ALLAY_GRADLE_PLUGIN_ID)
+            .map(|(alias, _)| format!("plugins.{}", alias));
+        libraries.chain(bundles).chain(plugins)
+    }
+
+    /// The Allay API version when the build script's accessor isn't known:
+    /// the first Allay library with a version, else `versions.allay` or
+    /// `versions.allay-api`.
+    fn allay_version(&self) -> Option<&str> {
+        let mut libraries: Vec<_> = self
+            .libraries
+            .iter()
+            .filter(|(_, lib)| lib.group == ALLAY_GROUP && lib.version.is_some())
+            .collect();
+        libraries.sort_by_key(|(alias, lib)| (lib.name != "api", alias.as_str()));
+        libraries
+            .first()
+            .and_then(|(_, lib)| lib.version.as_deref())
+            .or_else(|| self.version("allay"))
+            .or_else(|| self.version("allay-api"))
+    }
+}
+
+/// All catalogs of a build, by the name scripts access them with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VersionCatalogs {
+    catalogs: HashMap<String, VersionCatalog>,
+}
+
+impl VersionCatalogs {
+    pub fn insert(&mut self, name: &str, catalog: VersionCatalog) {
+        self.catalogs.insert(name.to_string(), catalog);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&VersionCatalog> {
+        self.catalogs.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.catalogs.is_empty()
+    }
+
+    /// Split an expression such as `libs.allay.api`, `rootProject.libs.allay`
+    /// or `platform(deps.bundles.allay).get()` into its catalog and the path
+    /// below it.
+    pub fn accessor(&self, expr: &str) -> Option<(&VersionCatalog, String)> {
+        for m in ACCESSOR_RE.find_iter(expr) {
+            let segments: Vec<&str> = m.as_str().split('.').map(str::trim).collect();
+            let Some(pos) = segments.iter().position(|s| self.catalogs.contains_key(*s)) else {
+                continue;
+            };
+            let mut rest = &segments[pos + 1..];
+            while let [init @ .., last] = rest
+                && ACCESSOR_SUFFIXES.contains(last)
+            {
+                rest = init;
+            }
+            if !rest.is_empty() {
+                return Some((&self.catalogs[segments[pos]], rest.join(".")));
+            }
+        }
+        None
+    }
+
+    /// The libraries `expr` refers to, if it is a catalog accessor.
+    pub fn libraries(&self, expr: &str) -> Vec<&CatalogLibrary> {
+        self.accessor(expr)
+            .map(|(catalog, path)| catalog.libraries_at(&path))
+            .unwrap_or_default()
+    }
+
+    /// The plugin `expr` refers to, e.g. `libs.plugins.allaygradle`.
+    pub fn plugin(&self, expr: &str) -> Option<&CatalogPlugin> {
+        let (catalog, path) = self.accessor(expr)?;
+        catalog.plugin(path.strip_prefix("plugins.")?)
+    }
+
+    /// Whether `script` uses an accessor that leads to an Allay library or
+    /// AllayGradle.
+    pub fn references_allay(&self, script: &str) -> bool {
+        self.catalogs.iter().any(|(name, catalog)| {
+            catalog
+                .allay_paths()
+                .any(|path| contains_ignore_case(script, &format!("{}.{}", name, path)))
+        })
+    }
+
+    /// Version of the Allay `artifact` (`api` or `server`) that `expr`
+    /// refers to, falling back to the default catalog's Allay version when
+    /// the accessor isn't in any catalog.
+    pub fn allay_version(&self, expr: &str, artifact: &str) -> Option<String> {
+        let libraries = self.libraries(expr);
+        if !libraries.is_empty() {
+            return libraries
+                .iter()
+                .find(|lib| lib.group == ALLAY_GROUP && lib.name == artifact)
+                .and_then(|lib| lib.version.clone());
+        }
+        self.get(DEFAULT_CATALOG)
+            .and_then(VersionCatalog::allay_version)
+            .map(str::to_string)
+    }
+}
+
+/// Custom catalogs declared in a settings script, as (name, path) pairs.
+pub fn settings_catalog_files(content: &str) -> Vec<(String, String)> {
+    SETTINGS_CATALOG_RE
+        .captures_iter(content)
+        .filter_map(|caps| {
+            let name = caps.name("created").or_else(|| caps.name("named"))?.as_str();
+            let path = caps.name("path")?.as_str();
+            Some((name.to_string(), path.trim_start_matches("./").to_string()))
+        })
+        .collect()
+}
+
+/// Resolve the catalog accessors the walkers found against `catalogs`,
+/// which settles whether they are Allay API or server dependencies,
+/// AllayGradle, and their versions.
+pub(crate) fn apply_catalogs(dsl: &mut AllayDsl, catalogs: &VersionCatalogs) {
+    if catalogs.is_empty() {
+        return;
+    }
+
+    let mut accessors = std::mem::take(&mut dsl.catalog_refs);
+    // The walkers guess API or server from the accessor name; the catalog
+    // knows better.
+    if let VersionRef::VersionCatalog(path) = &dsl.api_version_ref
+        && !catalogs.libraries(path).is_empty()
+    {
+        accessors.push(path.clone());
+        dsl.api = None;
+        dsl.api_version_ref = VersionRef::None;
+    }
+    if let VersionRef::VersionCatalog(path) = &dsl.server_version_ref
+        && !catalogs.libraries(path).is_empty()
+    {
+        accessors.push(path.clone());
+        dsl.server = None;
+        dsl.server_version_ref = VersionRef::None;
+    }
+
+    for accessor in &accessors {
+        if let Some(plugin) = catalogs.plugin(accessor) {
+            if plugin.id == ALLAY_GRADLE_PLUGIN_ID {
+                dsl.allay_gradle = Some(AllayGradlePlugin {
+                    version: plugin.version.clone(),
+                    alias: Some(accessor.clone()),
+                });
+            } else if dsl
+                .allay_gradle
+                .as_ref()
+                .is_some_and(|g| g.alias.as_ref() == Some(accessor))
+            {
+                dsl.allay_gradle = None;
+            }
+            continue;
+        }
+        for library in catalogs.libraries(accessor) {
+            if library.group != ALLAY_GROUP {
+                continue;
+            }
+            let version_ref = match &library.version {
+                Some(v) => VersionRef::Literal(v.clone()),
+                None => VersionRef::None,
+            };
+            match library.name.as_str() {
+                "api" => {
+                    dsl.has_allay_dependency = true;
+                    dsl.api = Some(library.version.clone().unwrap_or_default());
+                    dsl.api_version_ref = version_ref;
+                }
+                "server" => {
+                    dsl.has_allay_dependency = true;
+                    dsl.api_only = Some(false);
+                    dsl.server = Some(library.version.clone().unwrap_or_default());
+                    dsl.server_version_ref = version_ref;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn normalize_alias(alias: &str) -> String {
+    alias.replace(['-', '_'], ".").to_lowercase()
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// A version string, or the `strictly`/`require`/`prefer` of a rich version.
+fn rich_version(value: &toml::Value) -> Option<String> {
+    if let Some(v) = value.as_str() {
+        return Some(v.to_string());
+    }
+    ["strictly", "require", "prefer"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(|v| v.as_str()))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CATALOG: &str = r#"
+[versions]
+allay = "0.24.0"
+shadow = { strictly = "8.3.0" }
+
+[libraries]
+allay-server = { module = "org.allaymc.allay:server", version.ref = "allay" }
+core = { group = "org.allaymc.allay", name = "api", version = { ref = "allay" } }
+lombok = "org.projectlombok:lombok:1.18.34"
+
+[bundles]
+allay = ["core", "lombok"]
+
+[plugins]
+allaygradle = { id = "org.allaymc.gradle.plugin", version = "0.2.1" }
+shadow = { id = "com.gradleup.shadow", version.ref = "shadow" }
+"#;
+
+    fn catalogs() -> VersionCatalogs {
+        let mut catalogs = VersionCatalogs::default();
+        catalogs.insert("libs", VersionCatalog::parse(CATALOG).unwrap());
+        catalogs
+    }
+
+    #[test]
+    fn test_parse_catalog() {
+        let catalog = VersionCatalog::parse(CATALOG).unwrap();
+        let server = catalog.library("allay_server").unwrap();
+        assert_eq!(server.group, "org.allaymc.allay");
+        assert_eq!(server.name, "server");
+        assert_eq!(server.version.as_deref(), Some("0.24.0"));
+        assert_eq!(catalog.library("lombok").unwrap().version.as_deref(), Some("1.18.34"));
+        assert_eq!(catalog.plugin("shadow").unwrap().version.as_deref(), Some("8.3.0"));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let catalogs = catalogs();
+        assert_eq!(catalogs.libraries("libs.allay.server")[0].name, "server");
+        assert_eq!(catalogs.libraries("rootProject.libs.core.get()")[0].name, "api");
+        assert_eq!(catalogs.libraries("libs.bundles.allay").len(), 2);
+        assert!(catalogs.libraries("other.allay.server").is_empty());
+        assert_eq!(
+            catalogs.plugin("libs.plugins.allaygradle").unwrap().id,
+            "org.allaymc.gradle.plugin"
+        );
+        assert!(catalogs.references_allay("compileOnly(libs.bundles.allay)"));
+        assert!(!catalogs.references_allay("compileOnly(libs.lombok)"));
+    }
+
+    #[test]
+    fn test_allay_version_fallback() {
+        let catalogs = catalogs();
+        assert_eq!(catalogs.allay_version("libs.core", "api").as_deref(), Some("0.24.0"));
+        assert_eq!(catalogs.allay_version("libs.lombok", "api"), None);
+        assert_eq!(catalogs.allay_version("libs.allay.api", "api").as_deref(), Some("0.24.0"));
+    }
+
+    #[test]
+    fn test_settings_catalog_files() {
+        let kts = r#"
+dependencyResolutionManagement {
+    versionCatalogs {
+        create("deps") {
+            from(files("./gradle/deps.versions.toml"))
+        }
+    }
+}
+"#;
+        assert_eq!(
+            settings_catalog_files(kts),
+            vec![("deps".to_string(), "gradle/deps.versions.toml".to_string())]
+        );
+        let groovy =
+            "versionCatalogs {\n    deps {\n        from files('gradle/deps.toml')\n    }\n}";
+        assert_eq!(
+            settings_catalog_files(groovy),
+            vec![("deps".to_string(), "gradle/deps.toml".to_string())]
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::LazyLock;
+
+use super::types::{AllayDsl, PluginDsl};
+
+/// Builds whose precompiled script plugins the main build applies.
+const BUILD_LOGIC_DIRS: &[&str] = &["buildSrc/", "build-logic/"];
+
+/// Where precompiled script plugins live inside those builds.
+const SCRIPT_SOURCE_DIRS: &[&str] = &["src/main/kotlin/", "src/main/groovy/"];
+
+static PACKAGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?m)^\s*package\s+(?P<package>[\w.]+)"#).unwrap());
+
+/// `id("x")`, `id 'x'`, `` `x` `` and `apply(plugin = "x")`/`apply plugin: 'x'`.
+static PLUGIN_REQUEST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r#"\bid\s*\(?\s*["'](?P<id>[\w.-]+)["']"#,
+        r#"|`(?P<accessor>[\w.-]+)`"#,
+        r#"|\bapply\s*\(?\s*plugin\s*[:=]\s*["'](?P<applied>[\w.-]+)["']"#,
+    ))
+    .unwrap()
+});
+
+/// Whether `path` is a precompiled script plugin in `buildSrc` or
+/// `build-logic`, e.g. `buildSrc/src/main/kotlin/allay-conventions.gradle.kts`.
+pub fn is_script_plugin_path(path: &str) -> bool {
+    let Some(rest) = BUILD_LOGIC_DIRS.iter().find_map(|dir| path.strip_prefix(dir)) else {
+        return false;
+    };
+    (path.ends_with(".gradle.kts") || path.ends_with(".gradle"))
+        && SCRIPT_SOURCE_DIRS.iter().any(|dir| rest.contains(dir))
+}
+
+/// The id Gradle gives the precompiled script plugin at `path`: its file
+/// name without the extension, prefixed with the package of a Kotlin script.
+pub fn script_plugin_id(path: &str, content: &str) -> Option<String> {
+    let file_name = path.rsplit('/').next()?;
+    let (name, is_kts) = match file_name.strip_suffix(".gradle.kts") {
+        Some(name) => (name, true),
+        None => (file_name.strip_suffix(".gradle")?, false),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    let package = is_kts
+        .then(|| PACKAGE_RE.captures(content))
+        .flatten()
+        .map(|caps| caps["package"].to_string());
+    Some(match package {
+        Some(package) => format!("{}.{}", package, name),
+        None => name.to_string(),
+    })
+}
+
+/// Plugin ids `script` requests in `plugins {}` or with `apply`.
+fn requested_plugin_ids(script: &str) -> impl Iterator<Item = &str> {
+    PLUGIN_REQUEST_RE.captures_iter(script).filter_map(|caps| {
+        ["id", "accessor", "applied"]
+            .iter()
+            .find_map(|group| caps.name(group))
+            .map(|m| m.as_str())
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+struct ConventionPlugin {
+    dsl: AllayDsl,
+    /// Plugin ids the convention applies in turn.
+    applies: Vec<String>,
+}
+
+/// The precompiled script plugins of a build, by plugin id.
+#[derive(Debug, Clone, Default)]
+pub struct ConventionPlugins {
+    plugins: HashMap<String, ConventionPlugin>,
+}
+
+impl ConventionPlugins {
+    /// Add the convention plugin `id`, parsed from `script` into `dsl`.
+    pub fn insert(&mut self, id: &str, script: &str, dsl: AllayDsl) {
+        let applies = requested_plugin_ids(script)
+            .filter(|applied| *applied != id)
+            .map(str::to_string)
+            .collect();
+        self.plugins.insert(id.to_string(), ConventionPlugin { dsl, applies });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Whether `script` applies one of the convention plugins.
+    pub fn applied_by(&self, script: &str) -> bool {
+        requested_plugin_ids(script).any(|id| self.plugins.contains_key(id))
+    }
+
+    /// Merge the convention plugins `script` applies, and the ones those
+    /// apply, into `dsl`. What the script sets itself wins, then the nearest
+    /// convention.
+    pub fn apply(&self, script: &str, dsl: &mut AllayDsl) {
+        let mut queue: VecDeque<&str> = requested_plugin_ids(script).collect();
+        let mut seen = HashSet::new();
+        while let Some(id) = queue.pop_front() {
+            if !seen.insert(id) {
+                continue;
+            }
+            if let Some(convention) = self.plugins.get(id) {
+                inherit(dsl, &convention.dsl);
+                queue.extend(convention.applies.iter().map(String::as_str));
+            }
+        }
+    }
+}
+
+/// Fill what `dsl` leaves unset from `base`.
+fn inherit(dsl: &mut AllayDsl, base: &AllayDsl) {
+    if dsl.api.is_none() {
+        dsl.api = base.api.clone();
+        dsl.api_version_ref = base.api_version_ref.clone();
+    }
+    if dsl.server.is_none() {
+        dsl.server = base.server.clone();
+        dsl.server_version_ref = base.server_version_ref.clone();
+    }
+    if dsl.api_only.is_none() {
+        dsl.api_only = base.api_only;
+    }
+    dsl.has_allay_dependency |= base.has_allay_dependency;
+    if dsl.allay_gradle.is_none() {
+        dsl.allay_gradle = base.allay_gradle.clone();
+    }
+    for (field, inherited) in [
+        (&mut dsl.project_name, &base.project_name),
+        (&mut dsl.project_version, &base.project_version),
+        (&mut dsl.project_description, &base.project_description),
+    ] {
+        if field.is_none() {
+            *field = inherited.clone();
+        }
+    }
+
+    match (&mut dsl.plugin, &base.plugin) {
+        (None, Some(plugin)) => dsl.plugin = Some(plugin.clone()),
+        (Some(plugin), Some(inherited)) => inherit_plugin(plugin, inherited),
+        _ => {}
+    }
+}
+
+fn inherit_plugin(plugin: &mut PluginDsl, base: &PluginDsl) {
+    for (field, inherited) in [
+        (&mut plugin.entrance, &base.entrance),
+        (&mut plugin.name, &base.name),
+        (&mut plugin.version, &base.version),
+        (&mut plugin.description, &base.description),
+        (&mut plugin.website, &base.website),
+        (&mut plugin.api_version, &base.api_version),
+    ] {
+        if field.is_none() {
+            *field = inherited.clone();
+        }
+    }
+    if plugin.authors.is_empty() {
+        plugin.authors = base.authors.clone();
+    }
+    if plugin.dependencies.is_empty() {
+        plugin.dependencies = base.dependencies.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradle::parse_build_gradle_kts;
+
+    #[test]
+    fn test_script_plugin_paths() {
+        assert!(is_script_plugin_path("buildSrc/src/main/kotlin/allay-conventions.gradle.kts"));
+        assert!(is_script_plugin_path(
+            "build-logic/convention/src/main/groovy/allay-conventions.gradle"
+        ));
+        assert!(!is_script_plugin_path("buildSrc/build.gradle.kts"));
+        assert!(!is_script_plugin_path("core/src/main/kotlin/allay-conventions.gradle.kts"));
+
+        assert_eq!(
+            script_plugin_id("buildSrc/src/main/kotlin/allay-conventions.gradle.kts", "")
+                .as_deref(),
+            Some("allay-conventions")
+        );
+        assert_eq!(
+            script_plugin_id(
+                "buildSrc/src/main/kotlin/com/example/allay.gradle.kts",
+                "package com.example\n\nplugins { java }"
+            )
+            .as_deref(),
+            Some("com.example.allay")
+        );
+    }
+
+    #[test]
+    fn test_apply_transitively() {
+        let base = r#"allay {
+    api = "0.24.0"
+}"#;
+        let plugin = r#"plugins {
+    id("allay-base")
+}
+
+allay {
+    plugin {
+        authors += "Team"
+    }
+}"#;
+        let mut conventions = ConventionPlugins::default();
+        conventions.insert("allay-base", base, parse_build_gradle_kts(base).unwrap());
+        conventions.insert("allay-plugin", plugin, parse_build_gradle_kts(plugin).unwrap());
+
+        let script = r#"plugins {
+    `allay-plugin`
+}
+
+allay {
+    plugin {
+        name = "MyPlugin"
+    }
+}"#;
+        assert!(conventions.applied_by(script));
+        assert!(!conventions.applied_by("plugins {\n    id(\"java\")\n}"));
+
+        let mut dsl = parse_build_gradle_kts(script).unwrap();
+        conventions.apply(script, &mut dsl);
+        assert_eq!(dsl.api.as_deref(), Some("0.24.0"));
+        let plugin = dsl.plugin.unwrap();
+        assert_eq!(plugin.name.as_deref(), Some("MyPlugin"));
+        assert_eq!(plugin.authors, vec!["Team"]);
+    }
+}
@@ -2,8 +2,8 @@ use tree_sitter::Node;
 
 use super::dependency::extract_dependencies;
 use super::util::{
-    collect_string_values, extract_value, get_assignment_lhs_name, get_call_name, get_text,
-    with_closure,
+    collect_string_values, extract_assigned_value, get_assignment_lhs_name, get_call_name,
+    get_text, with_closure,
 };
 use crate::gradle::types::{AllayDsl, PluginDsl};
 
@@ -51,16 +51,15 @@ fn handle_allay_child(node: &Node, content: &str, dsl: &mut AllayDsl) {
 }
 
 fn handle_allay_assignment(node: &Node, content: &str, dsl: &mut AllayDsl) {
-    if let Some((key, op, value_node)) = parse_assignment_parts(node, content) {
-        if op == "=" {
-            if let Some(value) = extract_value(&value_node, content) {
-                match key.as_str() {
-                    "api" => dsl.api = Some(value),
-                    "apiOnly" => dsl.api_only = value.parse().ok(),
-                    "server" => dsl.server = Some(value),
-                    _ => {}
-                }
-            }
+    if let Some((key, op, value_node)) = parse_assignment_parts(node, content)
+        && op == "="
+        && let Some(value) = extract_assigned_value(&value_node, content)
+    {
+        match key.as_str() {
+            "api" => dsl.api = Some(value),
+            "apiOnly" => dsl.api_only = value.parse().ok(),
+            "server" => dsl.server = Some(value),
+            _ => {}
         }
     }
 }
@@ -134,7 +133,7 @@ fn handle_plugin_assignment(node: &Node, content: &str, plugin: &mut PluginDsl)
     if let Some((key, op, value_node)) = parse_assignment_parts(node, content) {
         if op == "=" {
             // Handle simple value assignment
-            if let Some(value) = extract_value(&value_node, content) {
+            if let Some(value) = extract_assigned_value(&value_node, content) {
                 match key.as_str() {
                     "entrance" => plugin.entrance = Some(value),
                     "name" => plugin.name = Some(value),
@@ -148,11 +147,8 @@ fn handle_plugin_assignment(node: &Node, content: &str, plugin: &mut PluginDsl)
 
             // Handle list assignment: `authors = ["a", "b"]` or `it.authors = ["Luck"]`
             let values = collect_string_values(&value_node, content);
-            if !values.is_empty() {
-                match key.as_str() {
-                    "authors" => plugin.authors.extend(values),
-                    _ => {}
-                }
+            if !values.is_empty() && key == "authors" {
+                plugin.authors.extend(values);
             }
         } else if op == "+=" {
             match key.as_str() {
@@ -246,7 +242,7 @@ fn parse_juxt_string_value(node: &Node, content: &str) -> Option<(String, String
                 let mut inner = child.walk();
                 for arg in child.children(&mut inner) {
                     if value.is_none() {
-                        value = extract_value(&arg, content);
+                        value = extract_assigned_value(&arg, content);
                     }
                 }
             }
@@ -1,6 +1,6 @@
 use tree_sitter::Node;
 
-use super::util::{CallArg, collect_call_args, get_call_name, get_field_access_path};
+use super::util::{CallArg, collect_call_args, get_call_name, get_field_access_path, get_text};
 use crate::gradle::types::{GradleDependency, VersionRef};
 
 pub enum AllayDepInfo {
@@ -100,6 +100,33 @@ pub fn extract_allay_dependency(node: &Node, content: &str) -> Option<AllayDepIn
     }
 }
 
+/// Non-literal arguments of a dependency configuration call, such as
+/// `libs.allay.api` or `platform(libs.bundles.allay)`, which may be version
+/// catalog accessors.
+pub fn catalog_candidates(node: &Node, content: &str) -> Vec<String> {
+    let Some(name) = get_call_name(node, content) else {
+        return Vec::new();
+    };
+    if !DEPENDENCY_CONFIGS.contains(&name.as_str()) {
+        return Vec::new();
+    }
+
+    let mut candidates = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.kind() != "argument_list" {
+            continue;
+        }
+        let mut inner = child.walk();
+        for arg in child.children(&mut inner) {
+            if matches!(arg.kind(), "field_access" | "method_invocation") {
+                candidates.push(get_text(&arg, content));
+            }
+        }
+    }
+    candidates
+}
+
 fn check_version_catalog_ref(node: &Node, content: &str) -> Option<AllayDepInfo> {
     let mut cursor = node.walk();
 
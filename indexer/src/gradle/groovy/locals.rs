@@ -0,0 +1,118 @@
+use tree_sitter::Node;
+
+use super::util::{
+    CallArg, collect_call_args, eval_string, extract_string, get_field_access_path, get_text,
+    with_closure,
+};
+use crate::gradle::properties::GradleProperties;
+
+/// Prefixes of an `ext` property on the left of an assignment.
+const EXT_PREFIXES: &[&str] = &["ext.", "project.ext.", "rootProject.ext."];
+
+/// Record the script's top-level `def`/typed local declarations and `ext`
+/// properties (`ext.x = ...`, `ext['x'] = ...`, `ext { x = ... }`,
+/// `ext.set('x', ...)`) in `scope`, in declaration order.
+pub fn collect_locals(node: &Node, content: &str, scope: &mut GradleProperties) {
+    let mut cursor = node.walk();
+
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "local_variable_declaration" => {
+                let mut decl_cursor = child.walk();
+                for declarator in child.children_by_field_name("declarator", &mut decl_cursor) {
+                    if let Some(name) = declarator.child_by_field_name("name")
+                        && let Some(value) = declarator.child_by_field_name("value")
+                        && let Some(value) = eval_string(&value, content)
+                    {
+                        scope.define(&get_text(&name, content), &value);
+                    }
+                }
+            }
+            "expression_statement" => {
+                let mut inner = child.walk();
+                for statement in child.children(&mut inner) {
+                    collect_statement(&statement, content, scope);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_statement(node: &Node, content: &str, scope: &mut GradleProperties) {
+    match node.kind() {
+        "assignment_expression" => {
+            let (Some(left), Some(right)) = (
+                node.child_by_field_name("left"),
+                node.child_by_field_name("right"),
+            ) else {
+                return;
+            };
+            let name = match left.kind() {
+                "field_access" => {
+                    let path = get_field_access_path(&left, content);
+                    EXT_PREFIXES
+                        .iter()
+                        .find_map(|prefix| path.strip_prefix(prefix))
+                        .map(str::to_string)
+                }
+                "array_access" => left
+                    .child_by_field_name("array")
+                    .filter(|a| get_text(a, content) == "ext")
+                    .and_then(|_| left.child_by_field_name("index"))
+                    .and_then(|i| extract_string(&i, content)),
+                _ => None,
+            };
+            if let Some(name) = name
+                && let Some(value) = eval_string(&right, content)
+            {
+                scope.define(&name, &value);
+            }
+        }
+        "method_invocation" => {
+            let object = node
+                .child_by_field_name("object")
+                .map(|o| get_text(&o, content));
+            let name = node
+                .child_by_field_name("name")
+                .map(|n| get_text(&n, content));
+            match (object.as_deref(), name.as_deref()) {
+                (None | Some("project"), Some("ext")) => {
+                    with_closure(node, |closure| collect_ext_block(closure, content, scope));
+                }
+                (Some("ext" | "project.ext"), Some("set")) => {
+                    if let [CallArg::Positional(name), CallArg::Positional(value), ..] =
+                        collect_call_args(node, content).as_slice()
+                    {
+                        scope.define(name, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `ext { x = ... }`: plain assignments inside the closure define `ext`
+/// properties.
+fn collect_ext_block(node: &Node, content: &str, scope: &mut GradleProperties) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        let assignment = match child.kind() {
+            "assignment_expression" => child,
+            "expression_statement" => match child.named_child(0) {
+                Some(inner) if inner.kind() == "assignment_expression" => inner,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        if let Some(left) = assignment.child_by_field_name("left")
+            && left.kind() == "identifier"
+            && let Some(right) = assignment.child_by_field_name("right")
+            && let Some(value) = eval_string(&right, content)
+        {
+            scope.define(&get_text(&left, content), &value);
+        }
+    }
+}
@@ -1,14 +1,17 @@
 mod allay_block;
 mod dependency;
+mod locals;
 #[cfg(test)]
 mod tests;
 mod util;
 
+use crate::gradle::allay_gradle::detect_allay_gradle;
 use crate::gradle::types::{AllayDsl, PluginDsl, VersionRef};
 use tree_sitter::Node;
 
 use allay_block::parse_allay_block;
 use dependency::extract_allay_dependency;
+pub use locals::collect_locals;
 use util::{get_call_path, get_text, with_closure};
 
 fn version_ref_to_string(version_ref: &VersionRef) -> String {
@@ -60,6 +63,8 @@ fn handle_call(node: &Node, content: &str, dsl: &mut AllayDsl) {
         let proj_name = dsl.project_name.take();
         let proj_ver = dsl.project_version.take();
         let proj_desc = dsl.project_description.take();
+        let catalog_refs = std::mem::take(&mut dsl.catalog_refs);
+        let allay_gradle = dsl.allay_gradle.take();
         *dsl = AllayDsl {
             has_allay_dependency: has_dep,
             api_version_ref: api_ref,
@@ -67,6 +72,8 @@ fn handle_call(node: &Node, content: &str, dsl: &mut AllayDsl) {
             project_name: proj_name,
             project_version: proj_ver,
             project_description: proj_desc,
+            catalog_refs,
+            allay_gradle,
             ..Default::default()
         };
         with_closure(node, |closure| {
@@ -78,6 +85,8 @@ fn handle_call(node: &Node, content: &str, dsl: &mut AllayDsl) {
             allay_block::parse_plugin_block(closure, content, &mut plugin);
         });
         dsl.plugin = Some(plugin);
+    } else if call_path == "plugins" || call_path == "apply" {
+        detect_allay_gradle(&get_text(node, content), dsl);
     } else if let Some(dep_info) = extract_allay_dependency(node, content) {
         dsl.has_allay_dependency = true;
         match dep_info {
@@ -92,6 +101,9 @@ fn handle_call(node: &Node, content: &str, dsl: &mut AllayDsl) {
             }
             dependency::AllayDepInfo::Unknown => {}
         }
+    } else {
+        dsl.catalog_refs
+            .extend(dependency::catalog_candidates(node, content));
     }
 }
 
@@ -113,7 +125,7 @@ fn try_parse_top_level_assignment(node: &Node, content: &str, dsl: &mut AllayDsl
                 found_eq = true;
             }
             _ if found_eq && value.is_none() => {
-                value = util::extract_value(&child, content);
+                value = util::extract_assigned_value(&child, content);
             }
             _ => {}
         }
@@ -343,3 +343,202 @@ dependencies {
         assert_eq!(plugin.version, None);
     }
 }
+
+mod gradle_properties {
+    use super::*;
+    use crate::gradle::{GradleProperties, VersionCatalogs, parse_build_gradle_with};
+
+    fn props() -> GradleProperties {
+        GradleProperties::parse("allayVersion=0.24.0\nversion=1.4.0\npluginName=Props\n")
+    }
+
+    #[test]
+    fn test_template_in_dependency() {
+        let content = r#"
+dependencies {
+    compileOnly "org.allaymc.allay:api:$allayVersion"
+    compileOnly "org.allaymc.allay:server:${allayVersion}"
+}
+"#;
+        let dsl =
+            parse_build_gradle_with(content, &props(), &VersionCatalogs::default()).unwrap();
+        assert_eq!(dsl.api, Some("0.24.0".to_string()));
+        assert_eq!(dsl.server, Some("0.24.0".to_string()));
+    }
+
+    #[test]
+    fn test_bare_identifier_version() {
+        let content = r#"
+dependencies {
+    compileOnly group: 'org.allaymc.allay', name: 'api', version: project.findProperty('allayVersion')
+}
+"#;
+        let dsl =
+            parse_build_gradle_with(content, &props(), &VersionCatalogs::default()).unwrap();
+        assert_eq!(dsl.api, Some("0.24.0".to_string()));
+    }
+
+    #[test]
+    fn test_plugin_fields_and_project_version() {
+        let content = r#"
+allay {
+    api = allayVersion
+    plugin {
+        entrance = '.Main'
+        name = pluginName
+        version = project.version
+    }
+}
+"#;
+        let dsl =
+            parse_build_gradle_with(content, &props(), &VersionCatalogs::default()).unwrap();
+        assert_eq!(dsl.api, Some("0.24.0".to_string()));
+        assert_eq!(dsl.project_version, Some("1.4.0".to_string()));
+        let plugin = dsl.plugin.unwrap();
+        assert_eq!(plugin.name, Some("Props".to_string()));
+        assert_eq!(plugin.version, Some("1.4.0".to_string()));
+    }
+
+    #[test]
+    fn test_unresolved_template_is_variable() {
+        let content = r#"
+dependencies {
+    compileOnly "org.allaymc.allay:api:$otherVersion"
+}
+"#;
+        let dsl = parse_build_gradle(content).unwrap();
+        assert!(matches!(&dsl.api_version_ref, VersionRef::Variable(v) if v == "otherVersion"));
+    }
+}
+
+mod local_variables {
+    use super::*;
+
+    #[test]
+    fn test_def_in_dependency_template() {
+        let content = r#"
+def allayVersion = '0.14.0'
+
+dependencies {
+    compileOnly "org.allaymc.allay:api:$allayVersion"
+}
+"#;
+        let dsl = parse_build_gradle(content).unwrap();
+        assert_eq!(dsl.api, Some("0.14.0".to_string()));
+        assert!(matches!(&dsl.api_version_ref, VersionRef::Literal(v) if v == "0.14.0"));
+    }
+
+    #[test]
+    fn test_typed_local_and_concatenation() {
+        let content = r#"
+String major = '0.14'
+def allayVersion = major + '.2'
+
+dependencies {
+    compileOnly('org.allaymc.allay:api:' + allayVersion)
+    compileOnly('org.allaymc.allay', 'server', allayVersion)
+}
+"#;
+        let dsl = parse_build_gradle(content).unwrap();
+        assert_eq!(dsl.api, Some("0.14.2".to_string()));
+        assert_eq!(dsl.server, Some("0.14.2".to_string()));
+    }
+
+    #[test]
+    fn test_ext_properties() {
+        let content = r#"
+ext.allayVersion = '0.15.0'
+project.ext.pluginName = "ExtPlugin"
+ext {
+    pluginVersion = "1.2.0"
+}
+ext['website'] = 'https://example.com'
+ext.set('author', 'Someone')
+
+allay {
+    api = allayVersion
+    plugin {
+        entrance = '.Main'
+        name = project.ext.pluginName
+        version = "${pluginVersion}"
+        website = ext.website
+        description = "By $author"
+    }
+}
+"#;
+        let dsl = parse_build_gradle(content).unwrap();
+        assert_eq!(dsl.api, Some("0.15.0".to_string()));
+        let plugin = dsl.plugin.unwrap();
+        assert_eq!(plugin.name, Some("ExtPlugin".to_string()));
+        assert_eq!(plugin.version, Some("1.2.0".to_string()));
+        assert_eq!(plugin.website, Some("https://example.com".to_string()));
+        assert_eq!(plugin.description, Some("By Someone".to_string()));
+    }
+}
+
+mod catalog_accessors {
+    use super::*;
+    use crate::gradle::{GradleProperties, VersionCatalog, VersionCatalogs, parse_build_gradle_with};
+
+    #[test]
+    fn test_alias_with_module_notation() {
+        let mut catalogs = VersionCatalogs::default();
+        let libs = VersionCatalog::parse(
+            r#"
+[libraries]
+allay-sdk = { module = "org.allaymc.allay:api", version = "0.24.0" }
+"#,
+        )
+        .unwrap();
+        catalogs.insert("libs", libs);
+
+        let content = r#"
+dependencies {
+    compileOnly(libs.allay.sdk)
+}
+"#;
+        let dsl =
+            parse_build_gradle_with(content, &GradleProperties::default(), &catalogs).unwrap();
+        assert_eq!(dsl.api, Some("0.24.0".to_string()));
+        assert!(matches!(&dsl.api_version_ref, VersionRef::Literal(v) if v == "0.24.0"));
+    }
+}
+
+mod allay_gradle_plugin {
+    use super::*;
+    use crate::gradle::parse_gradle_settings;
+
+    #[test]
+    fn test_plugin_without_allay_dependency() {
+        let content = r#"plugins {
+    id 'java-library'
+    id 'org.allaymc.gradle.plugin' version '0.2.1'
+}
+
+allay {
+    api = '0.23.0'
+}"#;
+        let dsl = parse_build_gradle(content).unwrap();
+        assert!(!dsl.has_allay_dependency);
+        assert_eq!(dsl.api, Some("0.23.0".to_string()));
+        assert_eq!(dsl.allay_gradle.unwrap().version.as_deref(), Some("0.2.1"));
+    }
+
+    #[test]
+    fn test_apply_plugin() {
+        let content = "apply plugin: 'org.allaymc.gradle.plugin'";
+        let dsl = parse_build_gradle(content).unwrap();
+        assert_eq!(dsl.allay_gradle.unwrap().version, None);
+    }
+
+    #[test]
+    fn test_plugin_management_in_settings() {
+        let content = r#"pluginManagement {
+    plugins {
+        id 'org.allaymc.gradle.plugin' version '0.2.1'
+    }
+}"#;
+        let dsl = parse_gradle_settings("settings.gradle", content);
+        assert_eq!(dsl.allay_gradle.unwrap().version.as_deref(), Some("0.2.1"));
+    }
+}
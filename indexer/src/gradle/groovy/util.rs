@@ -1,5 +1,7 @@
 use tree_sitter::Node;
 
+use crate::gradle::properties::property_reference;
+
 pub fn get_text(node: &Node, content: &str) -> String {
     content[node.byte_range()].to_string()
 }
@@ -145,6 +147,9 @@ pub fn extract_value(node: &Node, content: &str) -> Option<String> {
             }
         }
         _ => {
+            if let Some(name) = property_reference(&content[node.byte_range()]) {
+                return Some(format!("${{{}}}", name));
+            }
             let mut cursor = node.walk();
             for child in node.children(&mut cursor) {
                 if let Some(v) = extract_value(&child, content) {
@@ -156,6 +161,54 @@ pub fn extract_value(node: &Node, content: &str) -> Option<String> {
     }
 }
 
+/// Extract the right-hand side of an assignment. Unlike `extract_value`,
+/// references and concatenations are evaluated with `eval_string`.
+pub fn extract_assigned_value(node: &Node, content: &str) -> Option<String> {
+    eval_string(node, content).or_else(|| extract_value(node, content))
+}
+
+/// Evaluate a string expression into a template for
+/// `GradleProperties::interpolate`. Literals keep their `$x`/`${x}`
+/// templates, a bare identifier (a local or project property), `project.x`
+/// or property lookup becomes `${name}`, and `+` concatenates.
+pub fn eval_string(node: &Node, content: &str) -> Option<String> {
+    match node.kind() {
+        "string_literal" | "character_literal" => extract_string(node, content),
+        "identifier" => {
+            let text = get_text(node, content);
+            match text.as_str() {
+                "true" | "false" | "null" => None,
+                _ => Some(format!("${{{}}}", text)),
+            }
+        }
+        "field_access" => {
+            let path = get_field_access_path(node, content);
+            if path.starts_with("project.") || path.starts_with("rootProject.") {
+                Some(format!("${{{}}}", path))
+            } else {
+                property_reference(&content[node.byte_range()]).map(|n| format!("${{{}}}", n))
+            }
+        }
+        "parenthesized_expression" => {
+            let inner = node.named_child(0)?;
+            eval_string(&inner, content)
+        }
+        "binary_expression" => {
+            let mut cursor = node.walk();
+            let is_plus = node
+                .children(&mut cursor)
+                .any(|c| !c.is_named() && c.kind() == "+");
+            if !is_plus {
+                return None;
+            }
+            let left = eval_string(&node.child_by_field_name("left")?, content)?;
+            let right = eval_string(&node.child_by_field_name("right")?, content)?;
+            Some(format!("{}{}", left, right))
+        }
+        _ => property_reference(&content[node.byte_range()]).map(|n| format!("${{{}}}", n)),
+    }
+}
+
 fn unescape(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     let mut chars = s.chars().peekable();
@@ -194,11 +247,8 @@ pub fn collect_call_args(node: &Node, content: &str) -> Vec<CallArg> {
     let mut cursor = node.walk();
 
     for child in node.children(&mut cursor) {
-        match child.kind() {
-            "argument_list" => {
-                args.extend(collect_args_from_list(&child, content));
-            }
-            _ => {}
+        if child.kind() == "argument_list" {
+            args.extend(collect_args_from_list(&child, content));
         }
     }
 
@@ -235,7 +285,7 @@ fn collect_args_from_list(node: &Node, content: &str) -> Vec<CallArg> {
                 }
             }
             _ => {
-                if let Some(v) = extract_value(&child, content) {
+                if let Some(v) = extract_assigned_value(&child, content) {
                     args.push(CallArg::Positional(v));
                 }
             }
@@ -260,7 +310,7 @@ fn parse_map_item(node: &Node, content: &str) -> Option<(String, String)> {
                 found_colon = true;
             }
             _ if found_colon && value.is_none() => {
-                value = extract_value(&child, content);
+                value = extract_assigned_value(&child, content);
             }
             _ => {}
         }
@@ -286,10 +336,10 @@ pub fn collect_string_values(node: &Node, content: &str) -> Vec<String> {
         "array_literal" => {
             let mut cursor = node.walk();
             for child in node.children(&mut cursor) {
-                if child.kind() == "string_literal" || child.kind() == "character_literal" {
-                    if let Some(s) = extract_string(&child, content) {
-                        values.push(s);
-                    }
+                if (child.kind() == "string_literal" || child.kind() == "character_literal")
+                    && let Some(s) = extract_string(&child, content)
+                {
+                    values.push(s);
                 }
             }
         }
@@ -316,18 +366,15 @@ fn collect_call_string_args(node: &Node, content: &str) -> Vec<String> {
     let mut cursor = node.walk();
 
     for child in node.children(&mut cursor) {
-        match child.kind() {
-            "argument_list" => {
-                let mut inner = child.walk();
-                for arg in child.children(&mut inner) {
-                    if arg.kind() == "string_literal" || arg.kind() == "character_literal" {
-                        if let Some(s) = extract_string(&arg, content) {
-                            values.push(s);
-                        }
-                    }
+        if child.kind() == "argument_list" {
+            let mut inner = child.walk();
+            for arg in child.children(&mut inner) {
+                if (arg.kind() == "string_literal" || arg.kind() == "character_literal")
+                    && let Some(s) = extract_string(&arg, content)
+                {
+                    values.push(s);
                 }
             }
-            _ => {}
         }
     }
 
@@ -1,7 +1,9 @@
 use tree_sitter::Node;
 
 use super::dependency::extract_dependencies;
-use super::util::{collect_string_values, extract_value, get_call_name, get_text, with_lambda};
+use super::util::{
+    collect_string_values, extract_assigned_value, get_call_name, get_text, with_lambda,
+};
 use crate::gradle::types::{AllayDsl, PluginDsl};
 
 pub fn parse_allay_block(node: &Node, content: &str, dsl: &mut AllayDsl) {
@@ -105,7 +107,7 @@ fn parse_assignment(node: &Node, content: &str) -> Option<(String, String)> {
                 return None;
             }
             _ if is_simple_assign && rhs.is_none() => {
-                rhs = extract_value(&child, content);
+                rhs = extract_assigned_value(&child, content);
             }
             _ => {}
         }
@@ -95,6 +95,39 @@ pub fn extract_allay_dependency(node: &Node, content: &str) -> Option<AllayDepIn
     }
 }
 
+/// Non-literal arguments of a dependency configuration call, such as
+/// `libs.allay.api` or `platform(libs.bundles.allay)`, which may be version
+/// catalog accessors.
+pub fn catalog_candidates(node: &Node, content: &str) -> Vec<String> {
+    let Some(name) = get_call_name(node, content) else {
+        return Vec::new();
+    };
+    if !DEPENDENCY_CONFIGS.contains(&name.as_str()) {
+        return Vec::new();
+    }
+
+    let mut candidates = Vec::new();
+    collect_argument_exprs(node, content, &mut candidates);
+    candidates
+}
+
+fn collect_argument_exprs(node: &Node, content: &str, out: &mut Vec<String>) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "call_suffix" | "value_arguments" => collect_argument_exprs(&child, content, out),
+            "value_argument" => {
+                if let Some(expr) = child.named_child(0)
+                    && matches!(expr.kind(), "navigation_expression" | "call_expression")
+                {
+                    out.push(get_text(&expr, content));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 fn check_version_catalog_ref(node: &Node, content: &str) -> Option<AllayDepInfo> {
     let mut cursor = node.walk();
 
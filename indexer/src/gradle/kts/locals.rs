@@ -0,0 +1,77 @@
+use tree_sitter::Node;
+
+use super::util::{eval_string, extract_string, get_call_name, get_text};
+use crate::gradle::properties::GradleProperties;
+
+/// Record the script's top-level `val`/`var` declarations, `val x by
+/// extra(...)` and `extra["x"] = ...` in `scope`, in declaration order.
+pub fn collect_locals(node: &Node, content: &str, scope: &mut GradleProperties) {
+    let mut cursor = node.walk();
+
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "statements" => collect_locals(&child, content, scope),
+            "property_declaration" => {
+                let mut name = None;
+                let mut value = None;
+                let mut found_eq = false;
+                let mut inner_cursor = child.walk();
+                for inner in child.children(&mut inner_cursor) {
+                    match inner.kind() {
+                        "variable_declaration" => {
+                            let mut decl_cursor = inner.walk();
+                            name = inner
+                                .children(&mut decl_cursor)
+                                .find(|n| matches!(n.kind(), "identifier" | "simple_identifier"))
+                                .map(|n| get_text(&n, content));
+                        }
+                        "=" => found_eq = true,
+                        "property_delegate" => {
+                            // `val x by extra("1")`
+                            if let Some(call) = inner.named_child(0)
+                                && call.kind() == "call_expression"
+                                && get_call_name(&call, content).as_deref() == Some("extra")
+                            {
+                                value = first_arg_string(&call, content);
+                            }
+                        }
+                        _ if found_eq && value.is_none() => {
+                            value = eval_string(&inner, content);
+                        }
+                        _ => {}
+                    }
+                }
+                if let (Some(name), Some(value)) = (name, value) {
+                    scope.define(&name, &value);
+                }
+            }
+            "assignment" => {
+                // `extra["x"] = ...`
+                if let Some(left) = child.child_by_field_name("left")
+                    && left.kind() == "index_expression"
+                    && left.named_child(0).is_some_and(|n| get_text(&n, content) == "extra")
+                    && let Some(key) = left.named_child(1).and_then(|n| extract_string(&n, content))
+                    && let Some(right) = child.child_by_field_name("right")
+                    && let Some(value) = eval_string(&right, content)
+                {
+                    scope.define(&key, &value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn first_arg_string(node: &Node, content: &str) -> Option<String> {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "call_suffix" | "value_arguments" => return first_arg_string(&child, content),
+            "value_argument" => {
+                return child.named_child(0).and_then(|n| eval_string(&n, content));
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -1,15 +1,21 @@
 mod allay_block;
 mod dependency;
+mod locals;
 #[cfg(test)]
 mod tests;
 mod util;
 
+use crate::gradle::allay_gradle::detect_allay_gradle;
 use crate::gradle::types::{AllayDsl, PluginDsl, VersionRef};
 use tree_sitter::Node;
 
 use allay_block::parse_allay_block;
 use dependency::extract_allay_dependency;
-use util::{extract_string, get_call_path, get_navigation_path, get_text, with_lambda};
+pub use locals::collect_locals;
+use util::{
+    extract_assigned_value, extract_string, get_call_path, get_navigation_path, get_text,
+    with_lambda,
+};
 
 fn version_ref_to_string(version_ref: &VersionRef) -> String {
     match version_ref {
@@ -32,6 +38,8 @@ pub fn parse(node: &Node, content: &str, dsl: &mut AllayDsl) {
                     let proj_name = dsl.project_name.take();
                     let proj_ver = dsl.project_version.take();
                     let proj_desc = dsl.project_description.take();
+                    let catalog_refs = std::mem::take(&mut dsl.catalog_refs);
+                    let allay_gradle = dsl.allay_gradle.take();
                     *dsl = AllayDsl {
                         has_allay_dependency: has_dep,
                         api_version_ref: api_ref,
@@ -39,6 +47,8 @@ pub fn parse(node: &Node, content: &str, dsl: &mut AllayDsl) {
                         project_name: proj_name,
                         project_version: proj_ver,
                         project_description: proj_desc,
+                        catalog_refs,
+                        allay_gradle,
                         ..Default::default()
                     };
                     with_lambda(&child, |lambda| {
@@ -50,6 +60,8 @@ pub fn parse(node: &Node, content: &str, dsl: &mut AllayDsl) {
                         allay_block::parse_plugin_block(lambda, content, &mut plugin);
                     });
                     dsl.plugin = Some(plugin);
+                } else if call_path == "plugins" || call_path == "apply" {
+                    detect_allay_gradle(&get_text(&child, content), dsl);
                 } else if let Some(dep_info) = extract_allay_dependency(&child, content) {
                     dsl.has_allay_dependency = true;
                     match dep_info {
@@ -64,6 +76,9 @@ pub fn parse(node: &Node, content: &str, dsl: &mut AllayDsl) {
                         }
                         dependency::AllayDepInfo::Unknown => {}
                     }
+                } else {
+                    dsl.catalog_refs
+                        .extend(dependency::catalog_candidates(&child, content));
                 }
             }
             "property_declaration" | "assignment" => {
@@ -87,6 +102,7 @@ pub fn parse(node: &Node, content: &str, dsl: &mut AllayDsl) {
 fn try_parse_property_assignment(node: &Node, content: &str, property_name: &str) -> Option<String> {
     let mut cursor = node.walk();
     let mut found_property = false;
+    let mut found_eq = false;
     let mut property_value = None;
 
     for child in node.children(&mut cursor) {
@@ -101,10 +117,8 @@ fn try_parse_property_assignment(node: &Node, content: &str, property_name: &str
                     }
                 }
             }
-            "identifier" | "simple_identifier" => {
-                if get_text(&child, content) == property_name {
-                    found_property = true;
-                }
+            "identifier" | "simple_identifier" if get_text(&child, content) == property_name => {
+                found_property = true;
             }
             "directly_assignable_expression" => {
                 let mut inner_cursor = child.walk();
@@ -119,6 +133,13 @@ fn try_parse_property_assignment(node: &Node, content: &str, property_name: &str
             "string_literal" | "line_string_literal" | "multiline_string_literal" => {
                 property_value = extract_string(&child, content);
             }
+            "=" => {
+                found_eq = true;
+            }
+            // `version = property("pluginVersion") as String` and the like.
+            _ if found_eq && property_value.is_none() => {
+                property_value = extract_assigned_value(&child, content);
+            }
             _ => {}
         }
     }
@@ -134,10 +155,8 @@ fn try_parse_dotted_assignment(node: &Node, content: &str, dotted_name: &str) ->
 
     for child in node.children(&mut cursor) {
         match child.kind() {
-            "navigation_expression" => {
-                if get_navigation_path(&child, content) == dotted_name {
-                    found_property = true;
-                }
+            "navigation_expression" if get_navigation_path(&child, content) == dotted_name => {
+                found_property = true;
             }
             "directly_assignable_expression" => {
                 let mut inner_cursor = child.walk();
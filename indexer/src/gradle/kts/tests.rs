@@ -717,3 +717,338 @@ dependencies {
         );
     }
 }
+
+mod gradle_properties {
+    use crate::gradle::{
+        GradleProperties, VersionCatalogs, VersionRef, parse_build_gradle_kts_with,
+    };
+
+    fn props() -> GradleProperties {
+        GradleProperties::parse("allayVersion=0.24.0\nversion=1.4.0\npluginName=Props\n")
+    }
+
+    #[test]
+    fn test_template_in_dependency() {
+        let content = r#"dependencies {
+    compileOnly("org.allaymc.allay:api:$allayVersion")
+}"#;
+        let dsl =
+            parse_build_gradle_kts_with(content, &props(), &VersionCatalogs::default()).unwrap();
+        assert_eq!(dsl.api, Some("0.24.0".to_string()));
+        assert!(matches!(&dsl.api_version_ref, VersionRef::Literal(v) if v == "0.24.0"));
+    }
+
+    #[test]
+    fn test_property_call_in_dependency() {
+        let content = r#"dependencies {
+    compileOnly("org.allaymc.allay", "api", property("allayVersion") as String)
+    compileOnly("org.allaymc.allay:server:${providers.gradleProperty("allayVersion").get()}")
+}"#;
+        let dsl =
+            parse_build_gradle_kts_with(content, &props(), &VersionCatalogs::default()).unwrap();
+        assert_eq!(dsl.api, Some("0.24.0".to_string()));
+        assert_eq!(dsl.server, Some("0.24.0".to_string()));
+    }
+
+    #[test]
+    fn test_unresolved_reference_is_variable() {
+        let content = r#"dependencies {
+    compileOnly("org.allaymc.allay:api:${findProperty("other")}")
+}"#;
+        let dsl =
+            parse_build_gradle_kts_with(content, &props(), &VersionCatalogs::default()).unwrap();
+        assert!(matches!(&dsl.api_version_ref, VersionRef::Variable(v) if v == "other"));
+    }
+
+    #[test]
+    fn test_plugin_fields_and_project_version() {
+        let content = r#"
+description = property("missing") as String
+
+allay {
+    api = property("allayVersion") as String
+    plugin {
+        entrance = ".Main"
+        name = findProperty("pluginName") as String
+        version = project.version.toString()
+        authors += "$pluginName Team"
+    }
+}
+"#;
+        let dsl =
+            parse_build_gradle_kts_with(content, &props(), &VersionCatalogs::default()).unwrap();
+        assert_eq!(dsl.api, Some("0.24.0".to_string()));
+        assert_eq!(dsl.project_version, Some("1.4.0".to_string()));
+        assert_eq!(dsl.project_description, None);
+        let plugin = dsl.plugin.unwrap();
+        assert_eq!(plugin.name, Some("Props".to_string()));
+        assert_eq!(plugin.authors, vec!["Props Team"]);
+    }
+
+    #[test]
+    fn test_version_assigned_from_property() {
+        let content = r#"
+version = providers.gradleProperty("pluginVersion").get()
+
+dependencies {
+    compileOnly("org.allaymc.allay:api:0.24.0")
+}
+"#;
+        let props = GradleProperties::parse("pluginVersion=2.1.0\n");
+        let dsl =
+            parse_build_gradle_kts_with(content, &props, &VersionCatalogs::default()).unwrap();
+        assert_eq!(dsl.project_version, Some("2.1.0".to_string()));
+    }
+}
+
+mod local_variables {
+    use super::*;
+    use crate::gradle::{
+        GradleProperties, VersionCatalogs, VersionRef, parse_build_gradle_kts_with,
+    };
+
+    #[test]
+    fn test_val_in_dependency_template() {
+        let content = r#"
+val allayVersion = "0.14.0"
+
+dependencies {
+    compileOnly("org.allaymc.allay:api:$allayVersion")
+    compileOnly("org.allaymc.allay:server:${allayVersion}")
+}
+"#;
+        let dsl = parse_build_gradle_kts(content).unwrap();
+        assert_eq!(dsl.api, Some("0.14.0".to_string()));
+        assert_eq!(dsl.server, Some("0.14.0".to_string()));
+        assert!(matches!(&dsl.api_version_ref, VersionRef::Literal(v) if v == "0.14.0"));
+    }
+
+    #[test]
+    fn test_val_as_positional_argument() {
+        let content = r#"
+var allayVersion: String = "0.14.0"
+
+dependencies {
+    compileOnly("org.allaymc.allay", "api", allayVersion)
+}
+"#;
+        let dsl = parse_build_gradle_kts(content).unwrap();
+        assert_eq!(dsl.api, Some("0.14.0".to_string()));
+    }
+
+    #[test]
+    fn test_concatenation_and_chained_vals() {
+        let content = r#"
+val major = "0.14"
+val allayVersion = major + ".2"
+val coordinate = "org.allaymc.allay:api:" + allayVersion
+
+dependencies {
+    compileOnly(coordinate)
+    compileOnly("org.allaymc.allay:server:" + (allayVersion))
+}
+"#;
+        let dsl = parse_build_gradle_kts(content).unwrap();
+        assert_eq!(dsl.server, Some("0.14.2".to_string()));
+    }
+
+    #[test]
+    fn test_extra_properties() {
+        let content = r#"
+val pluginName by extra("ExtraPlugin")
+extra["allayVersion"] = "0.15.0"
+
+allay {
+    api = extra["allayVersion"] as String
+    plugin {
+        entrance = ".Main"
+        name = pluginName
+    }
+}
+"#;
+        let dsl = parse_build_gradle_kts(content).unwrap();
+        assert_eq!(dsl.api, Some("0.15.0".to_string()));
+        assert_eq!(dsl.plugin.unwrap().name, Some("ExtraPlugin".to_string()));
+    }
+
+    #[test]
+    fn test_vals_feed_allay_block() {
+        let content = r#"
+val allayVersion = "0.14.0"
+val pluginVersion = "1.0.0"
+
+allay {
+    api = allayVersion
+    plugin {
+        entrance = ".Main"
+        name = "Demo"
+        version = "$pluginVersion-beta"
+        website = "https://example.com/" + name
+    }
+}
+"#;
+        let dsl = parse_build_gradle_kts(content).unwrap();
+        assert_eq!(dsl.api, Some("0.14.0".to_string()));
+        let plugin = dsl.plugin.unwrap();
+        assert_eq!(plugin.version, Some("1.0.0-beta".to_string()));
+        // `name` isn't a script variable.
+        assert_eq!(plugin.website, None);
+    }
+
+    #[test]
+    fn test_val_shadows_gradle_property() {
+        let content = r#"
+val allayVersion = "0.14.0"
+
+dependencies {
+    compileOnly("org.allaymc.allay:api:$allayVersion")
+}
+"#;
+        let props = GradleProperties::parse("allayVersion=0.1.0\n");
+        let dsl =
+            parse_build_gradle_kts_with(content, &props, &VersionCatalogs::default()).unwrap();
+        assert_eq!(dsl.api, Some("0.14.0".to_string()));
+    }
+}
+
+mod catalog_accessors {
+    use crate::gradle::{
+        GradleProperties, VersionCatalog, VersionCatalogs, VersionRef, parse_build_gradle_kts_with,
+    };
+
+    fn catalogs() -> VersionCatalogs {
+        let mut catalogs = VersionCatalogs::default();
+        let deps = VersionCatalog::parse(
+            r#"
+[versions]
+allay = "0.24.0"
+
+[libraries]
+core = { module = "org.allaymc.allay:api", version.ref = "allay" }
+runtime = { module = "org.allaymc.allay:server", version.ref = "allay" }
+lombok = "org.projectlombok:lombok:1.18.34"
+
+[bundles]
+allay = ["core", "lombok"]
+"#,
+        )
+        .unwrap();
+        catalogs.insert("deps", deps);
+        catalogs
+    }
+
+    fn parse(content: &str) -> Option<crate::gradle::AllayDsl> {
+        parse_build_gradle_kts_with(content, &GradleProperties::default(), &catalogs())
+    }
+
+    #[test]
+    fn test_any_alias_of_allay_module() {
+        let content = r#"dependencies {
+    compileOnly(deps.core)
+    testImplementation(deps.runtime)
+    implementation(deps.runtime)
+}"#;
+        let dsl = parse(content).unwrap();
+        assert!(dsl.has_allay_dependency);
+        assert_eq!(dsl.api, Some("0.24.0".to_string()));
+        assert_eq!(dsl.server, Some("0.24.0".to_string()));
+        assert_eq!(dsl.api_only, Some(false));
+        assert!(matches!(&dsl.api_version_ref, VersionRef::Literal(v) if v == "0.24.0"));
+    }
+
+    #[test]
+    fn test_bundle() {
+        let content = r#"dependencies {
+    compileOnly(deps.bundles.allay)
+}"#;
+        let dsl = parse(content).unwrap();
+        assert_eq!(dsl.api, Some("0.24.0".to_string()));
+    }
+
+    #[test]
+    fn test_unrelated_alias_is_not_allay() {
+        let content = r#"dependencies {
+    compileOnly(deps.lombok)
+}"#;
+        assert!(parse(content).is_none());
+    }
+}
+
+mod allay_gradle_plugin {
+    use super::*;
+    use crate::gradle::{
+        GradleProperties, VersionCatalog, VersionCatalogs, parse_build_gradle_kts_with,
+        parse_gradle_settings,
+    };
+
+    #[test]
+    fn test_plugin_without_allay_dependency() {
+        let content = r#"plugins {
+    id("java-library")
+    id("org.allaymc.gradle.plugin") version "0.2.1"
+}
+
+allay {
+    api = "0.23.0"
+}"#;
+        let dsl = parse_build_gradle_kts(content).unwrap();
+        assert!(!dsl.has_allay_dependency);
+        assert_eq!(dsl.allay_gradle.unwrap().version.as_deref(), Some("0.2.1"));
+    }
+
+    #[test]
+    fn test_plugin_alone_is_recognised() {
+        let content = r#"plugins {
+    id("org.allaymc.gradle.plugin")
+}"#;
+        let dsl = parse_build_gradle_kts(content).unwrap();
+        assert_eq!(dsl.allay_gradle.unwrap().version, None);
+        assert!(parse_build_gradle_kts("plugins {\n    id(\"java\")\n}").is_none());
+    }
+
+    #[test]
+    fn test_version_from_property() {
+        let content = r#"plugins {
+    id("org.allaymc.gradle.plugin") version allayGradleVersion
+}"#;
+        let props = GradleProperties::parse("allayGradleVersion=0.2.2");
+        let dsl =
+            parse_build_gradle_kts_with(content, &props, &VersionCatalogs::default()).unwrap();
+        assert_eq!(dsl.allay_gradle.unwrap().version.as_deref(), Some("0.2.2"));
+    }
+
+    #[test]
+    fn test_catalog_alias() {
+        let mut catalogs = VersionCatalogs::default();
+        let libs = VersionCatalog::parse(
+            r#"
+[plugins]
+ag = { id = "org.allaymc.gradle.plugin", version = "0.2.1" }
+shadow = "com.gradleup.shadow:8.3.0"
+"#,
+        )
+        .unwrap();
+        catalogs.insert("libs", libs);
+
+        let content = "plugins {\n    alias(libs.plugins.ag)\n    alias(libs.plugins.shadow)\n}";
+        let dsl =
+            parse_build_gradle_kts_with(content, &GradleProperties::default(), &catalogs).unwrap();
+        let allay_gradle = dsl.allay_gradle.unwrap();
+        assert_eq!(allay_gradle.version.as_deref(), Some("0.2.1"));
+        assert_eq!(allay_gradle.alias.as_deref(), Some("libs.plugins.ag"));
+    }
+
+    #[test]
+    fn test_plugin_management_in_settings() {
+        let content = r#"pluginManagement {
+    plugins {
+        id("org.allaymc.gradle.plugin") version "0.2.1"
+    }
+}
+
+rootProject.name = "MyPlugin""#;
+        let dsl = parse_gradle_settings("settings.gradle.kts", content);
+        assert_eq!(dsl.allay_gradle.unwrap().version.as_deref(), Some("0.2.1"));
+        assert_eq!(dsl.project_name.as_deref(), Some("MyPlugin"));
+    }
+}
@@ -1,5 +1,7 @@
 use tree_sitter::Node;
 
+use crate::gradle::properties::property_reference;
+
 pub fn get_call_name(node: &Node, content: &str) -> Option<String> {
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
@@ -128,6 +130,9 @@ pub fn extract_value(node: &Node, content: &str) -> Option<String> {
             }
         }
         _ => {
+            if let Some(name) = property_reference(&content[node.byte_range()]) {
+                return Some(format!("${{{}}}", name));
+            }
             let mut cursor = node.walk();
             for child in node.children(&mut cursor) {
                 if let Some(v) = extract_value(&child, content) {
@@ -139,6 +144,56 @@ pub fn extract_value(node: &Node, content: &str) -> Option<String> {
     }
 }
 
+/// Like `extract_value`, but for the right-hand side of an assignment, where
+/// references and concatenations are evaluated with `eval_string`.
+pub fn extract_assigned_value(node: &Node, content: &str) -> Option<String> {
+    eval_string(node, content).or_else(|| extract_value(node, content))
+}
+
+/// Evaluate a string expression into a template for
+/// `GradleProperties::interpolate`. Literals keep their `$x`/`${x}`
+/// templates, a variable, `project.x` or property lookup becomes `${name}`,
+/// and `+` concatenates.
+pub fn eval_string(node: &Node, content: &str) -> Option<String> {
+    match node.kind() {
+        "string_literal" | "line_string_literal" | "multiline_string_literal" => {
+            extract_string(node, content)
+        }
+        "identifier" | "simple_identifier" => {
+            let text = get_text(node, content);
+            match text.as_str() {
+                "true" | "false" | "null" => None,
+                _ => Some(format!("${{{}}}", text)),
+            }
+        }
+        "navigation_expression" => {
+            let path = get_navigation_path(node, content);
+            if path.starts_with("project.") || path.starts_with("rootProject.") {
+                Some(format!("${{{}}}", path))
+            } else {
+                property_reference(&content[node.byte_range()]).map(|n| format!("${{{}}}", n))
+            }
+        }
+        "parenthesized_expression" => {
+            let inner = node.named_child(0)?;
+            eval_string(&inner, content)
+        }
+        "binary_expression" => {
+            let mut cursor = node.walk();
+            let is_plus = node
+                .children(&mut cursor)
+                .any(|c| !c.is_named() && c.kind() == "+");
+            if !is_plus {
+                return None;
+            }
+            let left = eval_string(&node.child_by_field_name("left")?, content)?;
+            let right = eval_string(&node.child_by_field_name("right")?, content)?;
+            Some(format!("{}{}", left, right))
+        }
+        _ => property_reference(&content[node.byte_range()]).map(|n| format!("${{{}}}", n)),
+    }
+}
+
 fn unescape(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     let mut chars = s.chars().peekable();
@@ -205,8 +260,8 @@ fn parse_value_argument(node: &Node, content: &str) -> Option<CallArg> {
                 let text = get_text(&child, content);
                 if !found_eq {
                     name = Some(text);
-                } else if text == "true" || text == "false" {
-                    value = Some(text);
+                } else {
+                    value = extract_assigned_value(&child, content);
                 }
             }
             "=" => {
@@ -223,7 +278,7 @@ fn parse_value_argument(node: &Node, content: &str) -> Option<CallArg> {
             }
             _ => {
                 if value.is_none() {
-                    value = extract_value(&child, content);
+                    value = extract_assigned_value(&child, content);
                 }
             }
         }
@@ -233,6 +288,7 @@ fn parse_value_argument(node: &Node, content: &str) -> Option<CallArg> {
         (Some(n), Some(v), true, _) => Some(CallArg::Named(n, v)),
         (_, Some(v), false, _) => Some(CallArg::Positional(v)),
         (Some(n), None, false, _) if n == "true" || n == "false" => Some(CallArg::Positional(n)),
+        (Some(n), None, false, None) => Some(CallArg::PositionalRef(n)),
         (_, None, false, Some(r)) => Some(CallArg::PositionalRef(r)),
         _ => None,
     }
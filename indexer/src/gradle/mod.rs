@@ -1,7 +1,24 @@
+mod allay_gradle;
+mod catalog;
+mod conventions;
 mod groovy;
 mod kts;
 mod parser;
+mod properties;
 mod types;
 
-pub use parser::{parse_build_gradle, parse_build_gradle_kts, parse_gradle_settings};
-pub use types::{AllayDsl, GradleDependency, PluginDsl, PluginJson, VersionRef, parse_plugin_json};
+pub use allay_gradle::ALLAY_GRADLE_PLUGIN_ID;
+pub use catalog::{
+    CatalogLibrary, CatalogPlugin, DEFAULT_CATALOG, VersionCatalog, VersionCatalogs,
+    settings_catalog_files,
+};
+pub use conventions::{ConventionPlugins, is_script_plugin_path, script_plugin_id};
+pub use parser::{
+    parse_build_gradle, parse_build_gradle_kts, parse_build_gradle_kts_with,
+    parse_build_gradle_with, parse_gradle_script, parse_gradle_settings,
+};
+pub use properties::GradleProperties;
+pub use types::{
+    AllayDsl, AllayGradlePlugin, GradleDependency, PluginDsl, PluginJson, VersionRef,
+    parse_plugin_json,
+};
@@ -1,43 +1,77 @@
 use tree_sitter::Parser;
 
+use super::catalog::{VersionCatalogs, apply_catalogs};
 use super::groovy;
 use super::kts;
+use super::properties::{GradleProperties, apply_properties};
 use super::types::AllayDsl;
 
 pub fn parse_build_gradle_kts(content: &str) -> Option<AllayDsl> {
-    let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_kotlin_ng::LANGUAGE.into())
-        .ok()?;
-    let tree = parser.parse(content, None)?;
+    parse_build_gradle_kts_with(content, &GradleProperties::default(), &VersionCatalogs::default())
+}
 
-    let mut dsl = AllayDsl::default();
+/// Parse a `build.gradle.kts`, resolving variables and property references
+/// against the script's own declarations and `props`, and version catalog
+/// accessors against `catalogs`.
+pub fn parse_build_gradle_kts_with(
+    content: &str,
+    props: &GradleProperties,
+    catalogs: &VersionCatalogs,
+) -> Option<AllayDsl> {
+    parse_gradle_script("build.gradle.kts", content, props, catalogs)
+        .filter(AllayDsl::is_allay_project)
+}
 
-    kts::parse(&tree.root_node(), content, &mut dsl);
+pub fn parse_build_gradle(content: &str) -> Option<AllayDsl> {
+    parse_build_gradle_with(content, &GradleProperties::default(), &VersionCatalogs::default())
+}
 
-    if dsl.api.is_some() || dsl.plugin.is_some() || dsl.has_allay_dependency {
-        Some(dsl)
-    } else {
-        None
-    }
+/// Parse a `build.gradle`, resolving variables and property references
+/// against the script's own declarations and `props`, and version catalog
+/// accessors against `catalogs`.
+pub fn parse_build_gradle_with(
+    content: &str,
+    props: &GradleProperties,
+    catalogs: &VersionCatalogs,
+) -> Option<AllayDsl> {
+    parse_gradle_script("build.gradle", content, props, catalogs)
+        .filter(AllayDsl::is_allay_project)
 }
 
-pub fn parse_build_gradle(content: &str) -> Option<AllayDsl> {
+/// Parse any Gradle script, Kotlin if `path` ends in `.gradle.kts` and Groovy
+/// otherwise, like `parse_build_gradle_kts_with`/`parse_build_gradle_with`
+/// but without requiring it to declare anything Allay. Convention plugins
+/// and the scripts that apply them are only judged once merged.
+pub fn parse_gradle_script(
+    path: &str,
+    content: &str,
+    props: &GradleProperties,
+    catalogs: &VersionCatalogs,
+) -> Option<AllayDsl> {
+    let is_kts = path.ends_with(".gradle.kts");
     let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_groovy::LANGUAGE.into())
-        .ok()?;
+    let lang = if is_kts {
+        tree_sitter_kotlin_ng::LANGUAGE.into()
+    } else {
+        tree_sitter_groovy::LANGUAGE.into()
+    };
+    parser.set_language(&lang).ok()?;
     let tree = parser.parse(content, None)?;
 
     let mut dsl = AllayDsl::default();
 
-    groovy::parse(&tree.root_node(), content, &mut dsl);
-
-    if dsl.api.is_some() || dsl.plugin.is_some() || dsl.has_allay_dependency {
-        Some(dsl)
+    let mut scope = props.clone();
+    if is_kts {
+        kts::collect_locals(&tree.root_node(), content, &mut scope);
+        kts::parse(&tree.root_node(), content, &mut dsl);
     } else {
-        None
+        groovy::collect_locals(&tree.root_node(), content, &mut scope);
+        groovy::parse(&tree.root_node(), content, &mut dsl);
     }
+    apply_properties(&mut dsl, &scope);
+    apply_catalogs(&mut dsl, catalogs);
+
+    Some(dsl)
 }
 
 /// Parse a Gradle settings file (settings.gradle or settings.gradle.kts) for
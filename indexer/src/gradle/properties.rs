@@ -0,0 +1,391 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use super::types::{AllayDsl, VersionRef};
+
+/// `property("x")`, `findProperty("x")`, `providers.gradleProperty("x")`,
+/// `extra["x"]` and `ext.x`, optionally on `project`/`rootProject` and
+/// followed by `.get()`, `as String`, `!!` and the like.
+static PROPERTY_REF_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r#"^(?:(?:project|rootProject)\.)?(?:"#,
+        r#"(?:findProperty|property)\(\s*["'](?P<call>[\w.-]+)["']\s*\)"#,
+        r#"|providers\.gradleProperty\(\s*["'](?P<provider>[\w.-]+)["']\s*\)"#,
+        r#"|(?:extra|ext)\[\s*["'](?P<index>[\w.-]+)["']\s*\]"#,
+        r#"|ext\.(?P<field>\w+))"#,
+        r#"(?:\.get\(\)|\.getOrNull\(\)|\.orNull|\.toString\(\)|!!|\s+as\s+String\??)*"#,
+        r#"(?:\s*\?:.*)?$"#,
+    ))
+    .unwrap()
+});
+
+/// Prefixes under which a project property is also reachable.
+const PROPERTY_PREFIXES: &[&str] = &[
+    "project.ext.",
+    "rootProject.ext.",
+    "project.",
+    "rootProject.",
+    "ext.",
+    "extra.",
+];
+
+/// Properties from `gradle.properties` files.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GradleProperties {
+    values: HashMap<String, String>,
+}
+
+impl GradleProperties {
+    /// Parse a Java properties file: `key=value`, `key: value` or
+    /// `key value`, `#`/`!` comments and `\` line continuations.
+    pub fn parse(content: &str) -> Self {
+        let mut values = HashMap::new();
+        let mut lines = content.lines();
+
+        while let Some(line) = lines.next() {
+            let mut logical = line.trim_start().to_string();
+            if logical.is_empty() || logical.starts_with('#') || logical.starts_with('!') {
+                continue;
+            }
+            while ends_with_continuation(&logical) {
+                logical.pop();
+                match lines.next() {
+                    Some(next) => logical.push_str(next.trim_start()),
+                    None => break,
+                }
+            }
+
+            let (key, value) = split_entry(&logical);
+            if !key.is_empty() {
+                values.insert(unescape(key), unescape(value).trim_end().to_string());
+            }
+        }
+
+        Self { values }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, name: &str, value: &str) {
+        self.values.insert(name.to_string(), value.to_string());
+    }
+
+    /// Define a script variable as `template` evaluated against what is
+    /// already defined, so later declarations can build on earlier ones.
+    /// Values that don't resolve are left undefined.
+    pub fn define(&mut self, name: &str, template: &str) {
+        if let Some(value) = self.interpolate(template) {
+            self.values.insert(name.to_string(), value);
+        }
+    }
+
+    /// Add the properties of `other`, overriding existing ones. Used to layer
+    /// a module's `gradle.properties` over the root one.
+    pub fn extend(&mut self, other: &GradleProperties) {
+        for (k, v) in &other.values {
+            self.values.insert(k.clone(), v.clone());
+        }
+    }
+
+    /// Look up the property an expression such as `allayVersion`,
+    /// `project.version` or `property("allayVersion")` refers to.
+    pub fn resolve(&self, expr: &str) -> Option<&str> {
+        let expr = expr.trim();
+        if let Some(name) = property_reference(expr) {
+            return self.get(name);
+        }
+        if let Some(v) = self.get(expr) {
+            return Some(v);
+        }
+        PROPERTY_PREFIXES
+            .iter()
+            .find_map(|prefix| expr.strip_prefix(prefix))
+            .and_then(|name| self.get(name))
+    }
+
+    /// Substitute `$name` and `${expr}` templates. Returns None if any of
+    /// them doesn't resolve.
+    pub fn interpolate(&self, value: &str) -> Option<String> {
+        if !value.contains('$') {
+            return Some(value.to_string());
+        }
+
+        let mut result = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(pos) = rest.find('$') {
+            result.push_str(&rest[..pos]);
+            let after = &rest[pos + 1..];
+            if let Some(body) = after.strip_prefix('{') {
+                let end = body.find('}')?;
+                result.push_str(self.resolve(&body[..end])?);
+                rest = &body[end + 1..];
+            } else {
+                let len = template_name_len(after);
+                if len == 0 {
+                    // A lone `$`, e.g. in "costs $5".
+                    result.push('$');
+                    rest = after;
+                    continue;
+                }
+                result.push_str(self.resolve(&after[..len])?);
+                rest = &after[len..];
+            }
+        }
+        result.push_str(rest);
+        Some(result)
+    }
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1
+}
+
+/// Split a logical line at the first unescaped `=`, `:` or whitespace.
+fn split_entry(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '=' | ':' => return (&line[..i], line[i + 1..].trim_start()),
+            c if c.is_whitespace() => {
+                let value = line[i..].trim_start();
+                let value = value
+                    .strip_prefix(['=', ':'])
+                    .map_or(value, str::trim_start);
+                return (&line[..i], value);
+            }
+            _ => {}
+        }
+    }
+    (line, "")
+}
+
+fn unescape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                if let Some(c) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    result.push(c);
+                }
+            }
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+
+    result
+}
+
+/// Length of the `$name` template at the start of `s`: an identifier, or
+/// `project.name`.
+fn template_name_len(s: &str) -> usize {
+    let ident_len = |s: &str| {
+        if s.starts_with(|c: char| c.is_ascii_digit()) {
+            return 0;
+        }
+        s.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(s.len())
+    };
+
+    let len = ident_len(s);
+    if matches!(&s[..len], "project" | "rootProject") && s[len..].starts_with('.') {
+        let next = ident_len(&s[len + 1..]);
+        if next > 0 {
+            return len + 1 + next;
+        }
+    }
+    len
+}
+
+/// The property name a Gradle property lookup expression reads, e.g.
+/// `allayVersion` for `providers.gradleProperty("allayVersion").get()`.
+pub fn property_reference(expr: &str) -> Option<&str> {
+    let caps = PROPERTY_REF_RE.captures(expr.trim())?;
+    ["call", "provider", "index", "field"]
+        .iter()
+        .find_map(|group| caps.name(group))
+        .map(|m| m.as_str())
+}
+
+/// The expression of a value that is exactly one template, like
+/// `$allayVersion` or `${property("allayVersion")}`.
+fn single_reference(value: &str) -> Option<&str> {
+    if let Some(body) = value.strip_prefix("${").and_then(|b| b.strip_suffix('}')) {
+        if body.contains('}') {
+            return None;
+        }
+        return Some(property_reference(body).unwrap_or(body));
+    }
+    let name = value.strip_prefix('$')?;
+    (template_name_len(name) == name.len() && !name.is_empty()).then_some(name)
+}
+
+/// Substitute property templates in everything the walkers extracted.
+///
+/// `project.version` and `project.description` fall back to the `version`
+/// and `description` properties. An API or server version that is a single
+/// unresolved reference becomes a `VersionRef::Variable` so the builder can
+/// look further, and a `VersionRef::Variable` that `props` defines is
+/// resolved; unresolved plugin fields and AllayGradle versions are dropped.
+pub(crate) fn apply_properties(dsl: &mut AllayDsl, props: &GradleProperties) {
+    dsl.project_version = match dsl.project_version.take() {
+        Some(v) => props.interpolate(&v),
+        None => props.get("version").map(str::to_string),
+    };
+    dsl.project_description = match dsl.project_description.take() {
+        Some(d) => props.interpolate(&d),
+        None => props.get("description").map(str::to_string),
+    };
+
+    resolve_version_field(&mut dsl.api, &mut dsl.api_version_ref, props);
+    resolve_version_field(&mut dsl.server, &mut dsl.server_version_ref, props);
+    if let Some(allay_gradle) = &mut dsl.allay_gradle {
+        allay_gradle.version = allay_gradle.version.take().and_then(|v| props.interpolate(&v));
+    }
+
+    let Some(plugin) = &mut dsl.plugin else {
+        return;
+    };
+    let mut scope = props.clone();
+    if let Some(v) = &dsl.project_version {
+        scope.insert("version", v);
+    }
+    if let Some(d) = &dsl.project_description {
+        scope.insert("description", d);
+    }
+
+    for field in [
+        &mut plugin.entrance,
+        &mut plugin.name,
+        &mut plugin.version,
+        &mut plugin.description,
+        &mut plugin.website,
+        &mut plugin.api_version,
+    ] {
+        *field = field.take().and_then(|v| scope.interpolate(&v));
+    }
+    plugin.authors = std::mem::take(&mut plugin.authors)
+        .into_iter()
+        .filter_map(|a| scope.interpolate(&a))
+        .collect();
+    for dep in &mut plugin.dependencies {
+        dep.version = dep.version.take().and_then(|v| scope.interpolate(&v));
+    }
+}
+
+fn resolve_version_field(
+    value: &mut Option<String>,
+    version_ref: &mut VersionRef,
+    props: &GradleProperties,
+) {
+    if let VersionRef::Variable(name) = version_ref
+        && let Some(resolved) = props.resolve(name)
+    {
+        *value = Some(resolved.to_string());
+        *version_ref = VersionRef::Literal(resolved.to_string());
+        return;
+    }
+
+    let Some(v) = value.as_deref() else {
+        return;
+    };
+    if !v.contains('$') {
+        return;
+    }
+
+    if let Some(resolved) = props.interpolate(v) {
+        if matches!(version_ref, VersionRef::Literal(_)) {
+            *version_ref = VersionRef::Literal(resolved.clone());
+        }
+        *value = Some(resolved);
+    } else if let Some(name) = single_reference(v) {
+        *version_ref = VersionRef::Variable(name.to_string());
+        *value = Some(String::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_properties() {
+        let props = GradleProperties::parse(
+            "# comment\n\
+             ! also a comment\n\
+             allayVersion=0.24.0\n\
+             version : 1.2.0  \n\
+             description A plugin\n\
+             org.gradle.jvmargs=-Xmx2g \\\n    -Dfile.encoding=UTF-8\n\
+             path=C\\:\\\\tools\n",
+        );
+        assert_eq!(props.get("allayVersion"), Some("0.24.0"));
+        assert_eq!(props.get("version"), Some("1.2.0"));
+        assert_eq!(props.get("description"), Some("A plugin"));
+        assert_eq!(
+            props.get("org.gradle.jvmargs"),
+            Some("-Xmx2g -Dfile.encoding=UTF-8")
+        );
+        assert_eq!(props.get("path"), Some("C:\\tools"));
+    }
+
+    #[test]
+    fn test_property_reference() {
+        assert_eq!(property_reference(r#"property("a")"#), Some("a"));
+        assert_eq!(property_reference("project.findProperty('a')"), Some("a"));
+        assert_eq!(
+            property_reference(r#"providers.gradleProperty("a").get()"#),
+            Some("a")
+        );
+        assert_eq!(property_reference(r#"extra["a"] as String"#), Some("a"));
+        assert_eq!(property_reference("rootProject.ext.a"), Some("a"));
+        assert_eq!(property_reference(r#"property("a") + "b""#), None);
+        assert_eq!(property_reference("allayVersion"), None);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let props = GradleProperties::parse("allayVersion=0.24.0\nversion=1.0\n");
+        assert_eq!(props.interpolate("$allayVersion").as_deref(), Some("0.24.0"));
+        assert_eq!(
+            props.interpolate("v${project.version}-$allayVersion").as_deref(),
+            Some("v1.0-0.24.0")
+        );
+        assert_eq!(
+            props.interpolate(r#"${property("allayVersion")}"#).as_deref(),
+            Some("0.24.0")
+        );
+        assert_eq!(props.interpolate("costs $5").as_deref(), Some("costs $5"));
+        assert_eq!(props.interpolate("$missing"), None);
+    }
+
+    #[test]
+    fn test_unresolved_version_becomes_variable() {
+        let mut dsl = AllayDsl {
+            api: Some("$allayVersion".to_string()),
+            api_version_ref: VersionRef::Literal("$allayVersion".to_string()),
+            ..Default::default()
+        };
+        apply_properties(&mut dsl, &GradleProperties::default());
+        assert_eq!(dsl.api, Some(String::new()));
+        assert!(matches!(&dsl.api_version_ref, VersionRef::Variable(v) if v == "allayVersion"));
+    }
+}
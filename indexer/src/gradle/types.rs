@@ -27,6 +27,32 @@ pub struct AllayDsl {
     pub project_version: Option<String>,
     #[serde(skip)]
     pub project_description: Option<String>,
+    /// Version catalog accessors passed to dependency configurations or
+    /// `alias(...)`, for `VersionCatalogs` to tell whether they are Allay
+    /// artifacts or AllayGradle.
+    #[serde(skip)]
+    pub catalog_refs: Vec<String>,
+    #[serde(skip)]
+    pub allay_gradle: Option<AllayGradlePlugin>,
+}
+
+/// The AllayGradle plugin as applied by a build script.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AllayGradlePlugin {
+    pub version: Option<String>,
+    /// Catalog accessor when applied with `alias(...)`.
+    pub alias: Option<String>,
+}
+
+impl AllayDsl {
+    /// Whether the script declares anything Allay: an API version, a plugin,
+    /// an Allay dependency or AllayGradle.
+    pub fn is_allay_project(&self) -> bool {
+        self.api.is_some()
+            || self.plugin.is_some()
+            || self.has_allay_dependency
+            || self.allay_gradle.is_some()
+    }
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -1,11 +1,27 @@
-use allayindexer::github::{client, init_client};
-use allayindexer::plugin::{delete_plugin, load_plugins, write_plugin};
+use allayindexer::cache::{
+    BLOB_CACHE_FILE, CACHE_FILE, CACHE_VERSION, CacheCounts, DEFAULT_MAX_AGE_DAYS, DataCache,
+    PruneOptions, file_size,
+};
+use allayindexer::github::{
+    DEFAULT_CONCURRENCY, DEFAULT_MAX_PAGES, GitHubClient, client, init_client, init_client_with,
+};
+use allayindexer::plugin::tombstones::{DEFAULT_DELETE_AFTER_DAYS, DEFAULT_DELETE_AFTER_RUNS};
+use allayindexer::plugin::{DeletionPolicy, IndexStats, Plugin, Tombstones};
+use allayindexer::plugin::{
+    delete_plugin, load_plugins, validate_index, write_plugin, write_plugin_metadata,
+};
 use allayindexer::search::build_orama_index;
-use allayindexer::sync::{discover_new_plugins, update_existing_plugins};
+use allayindexer::sync::explain::skip_reason;
+use allayindexer::sync::{
+    ExplainLayer, ExplainStep, RunReport, UpdateOptions, build_plugins_from_local,
+    discover_new_plugins, inspect_repo, update_existing_plugins,
+};
 use allayindexer::util::{
-    clear_processed_ids, extract_repo_full_name, has_flag, read_last_sync, read_processed_ids,
-    write_last_sync, write_processed_ids,
+    RecordSession, clear_processed_ids, extract_repo_full_name, get_arg, has_flag, read_last_sync,
+    read_processed_ids, read_record_session, write_last_sync, write_processed_ids,
+    write_record_session,
 };
+use serde::Serialize;
 use std::collections::HashSet;
 use std::env;
 use std::fs;
@@ -22,12 +38,16 @@ fn main() {
     }
 
     let debug = has_flag(&args, "--debug");
-    init_tracing(debug);
+    let explain = (args[1] == "inspect").then(ExplainLayer::new);
+    init_tracing(debug, explain.clone());
 
     match args[1].as_str() {
         "build" => cmd_build(),
         "update" => cmd_update(&args[2..]),
         "discover" => cmd_discover(&args[2..]),
+        "validate" => cmd_validate(&args[2..]),
+        "inspect" => cmd_inspect(&args[2..], explain.unwrap_or_default()),
+        "cache" => cmd_cache(&args[2..]),
         "help" | "--help" | "-h" => print_usage(),
         _ => {
             error!(command = %args[1], "Unknown command");
@@ -37,7 +57,9 @@ fn main() {
     }
 }
 
-fn init_tracing(debug: bool) {
+fn init_tracing(debug: bool, explain: Option<ExplainLayer>) {
+    use tracing_subscriber::filter::LevelFilter;
+    use tracing_subscriber::prelude::*;
     use tracing_subscriber::{EnvFilter, fmt};
 
     let filter = if debug {
@@ -46,14 +68,19 @@ fn init_tracing(debug: bool) {
         EnvFilter::new("info")
     };
 
-    fmt()
-        .with_env_filter(filter)
+    let fmt_layer = fmt::layer()
         .with_target(false)
+        .with_writer(std::io::stderr)
         .with_span_events(if debug {
             fmt::format::FmtSpan::CLOSE
         } else {
             fmt::format::FmtSpan::NONE
         })
+        .with_filter(filter);
+
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(explain.map(|layer| layer.with_filter(LevelFilter::DEBUG)))
         .init();
 }
 
@@ -64,11 +91,46 @@ fn print_usage() {
     println!("  allayindexer build                    Build search index only");
     println!("  allayindexer update [OPTIONS]         Update existing plugins");
     println!("  allayindexer discover [OPTIONS]       Discover new plugins");
+    println!("  allayindexer validate [OPTIONS]       Lint index files, print a JSON report");
+    println!("  allayindexer inspect <OWNER/REPO>     Explain how a repository is indexed");
+    println!("  allayindexer inspect --path <DIR>     Print plugin JSON for a local checkout");
+    println!("  allayindexer cache stats|prune|clear  Show, prune or delete the API cache");
     println!();
     println!("Options:");
     println!("  --force                      Force full run (ignore saved state)");
     println!("  --dry-run                    Preview changes without applying");
     println!("  --debug                      Enable debug logging");
+    println!("  --record <DIR>               Save every GitHub response to DIR");
+    println!("  --replay <DIR>               Serve GitHub responses from DIR (no network, no writes)");
+    println!("  --report <FILE>              Write a JSON summary of the run to FILE");
+    println!(
+        "  --ignore-fields <LIST>       Comma-separated fields that alone don't count as an update"
+    );
+    println!(
+        "  --max-pages <N>              Pages fetched per release/contributor listing (default {})",
+        DEFAULT_MAX_PAGES
+    );
+    println!(
+        "  --concurrency <N>            Repositories processed in parallel (default {})",
+        DEFAULT_CONCURRENCY
+    );
+    println!("  --wait-for-reset             Sleep until the rate limit resets instead of stopping");
+    println!("  --max-api-calls <N>          Stop after N calls charged to the hourly quota");
+    println!("  --max-deletions <N>          Delete at most N plugins per update run");
+    println!(
+        "  --delete-after-runs <N>      Delete a missing plugin once missing on N days (default {})",
+        DEFAULT_DELETE_AFTER_RUNS
+    );
+    println!(
+        "  --delete-after-days <N>      ...or N days after it went missing (default {})",
+        DEFAULT_DELETE_AFTER_DAYS
+    );
+    println!(
+        "  --max-age-days <N>           cache prune: drop entries unused for N days (default {})",
+        DEFAULT_MAX_AGE_DAYS
+    );
+    println!("  --keep-unreferenced          cache prune: keep entries of repos not in the index");
+    println!("  --strict-dependencies        validate: unindexed dependencies are errors");
     println!();
     println!("Authentication (choose one):");
     println!("  --token <TOKEN>              Personal access token (or GITHUB_TOKEN env)");
@@ -84,7 +146,6 @@ fn cmd_build() {
 
     let index_dir = Path::new("AllayHubIndex");
     let output_file = Path::new("src/public/orama-index.bin");
-    let builder_path = Path::new("orama_builder.mjs");
 
     if !index_dir.exists() {
         error!(path = ?index_dir, "Index directory not found");
@@ -103,7 +164,7 @@ fn cmd_build() {
 
     {
         let _span = info_span!("build_orama").entered();
-        if !build_orama_index(&plugins, output_file, builder_path) {
+        if !build_orama_index(&plugins, output_file) {
             process::exit(1);
         }
     }
@@ -114,6 +175,116 @@ fn cmd_build() {
     }
 }
 
+fn cmd_validate(args: &[String]) {
+    let _span = info_span!("validate").entered();
+
+    let index_dir = Path::new("AllayHubIndex");
+    if !index_dir.exists() {
+        error!(path = ?index_dir, "Index directory not found");
+        process::exit(1);
+    }
+
+    let report = validate_index(index_dir, has_flag(args, "--strict-dependencies"));
+    for issue in &report.issues {
+        warn!(path = %issue.path, check = issue.check, "{}", issue.message);
+    }
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => error!(error = %e, "Failed to serialize report"),
+    }
+
+    info!(
+        files = report.files,
+        errors = report.errors,
+        warnings = report.warnings,
+        "Validation finished"
+    );
+    if !report.is_ok() {
+        process::exit(1);
+    }
+}
+
+/// Output of `inspect <owner/repo>`.
+#[derive(Serialize)]
+struct InspectReport {
+    repo: String,
+    steps: Vec<ExplainStep>,
+    plugins: Vec<Plugin>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+fn cmd_inspect(args: &[String], explain: ExplainLayer) {
+    let _span = info_span!("inspect").entered();
+
+    let path = get_arg(args, "--path");
+    let repo = args.first().filter(|a| !a.starts_with("--")).cloned();
+    if path.is_none() && repo.is_none() {
+        error!("inspect requires <OWNER/REPO> or --path <DIR>");
+        print_usage();
+        process::exit(1);
+    }
+
+    if path.is_some() {
+        // Only needed to resolve branch snapshot API versions; works without a token.
+        let token = get_arg(args, "--token").or_else(|| env::var("GITHUB_TOKEN").ok());
+        if let Err(e) = init_client_with(GitHubClient::new(token)) {
+            error!(error = %e, "Failed to create client");
+            process::exit(1);
+        }
+    } else if let Err(e) = init_client(args) {
+        warn!(error = %e, "Falling back to unauthenticated requests (60 req/hour)");
+        if let Err(e) = init_client_with(GitHubClient::new(None)) {
+            error!(error = %e, "Failed to create client");
+            process::exit(1);
+        }
+    }
+
+    let Some(path) = path else {
+        let repo = repo.unwrap_or_default();
+        let (plugins, reason) = match inspect_repo(&repo) {
+            Ok(plugins) => (plugins, None),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        };
+        let steps = explain.take();
+        let reason = if plugins.is_empty() {
+            reason.or_else(|| skip_reason(&steps))
+        } else {
+            None
+        };
+        let report = InspectReport {
+            repo,
+            steps,
+            plugins,
+            reason,
+        };
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => error!(error = %e, "Failed to serialize report"),
+        }
+        if report.plugins.is_empty() {
+            process::exit(1);
+        }
+        return;
+    };
+
+    let plugins = match build_plugins_from_local(Path::new(&path)) {
+        Ok(p) => p,
+        Err(e) => {
+            error!(path = %path, error = %e, "No plugin produced");
+            process::exit(1);
+        }
+    };
+
+    match serde_json::to_string_pretty(&plugins) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            error!(error = %e, "Failed to serialize plugins");
+            process::exit(1);
+        }
+    }
+}
+
 fn cmd_update(args: &[String]) {
     let _span = info_span!("update").entered();
 
@@ -124,6 +295,12 @@ fn cmd_update(args: &[String]) {
 
     let dry_run = has_flag(args, "--dry-run");
     let force = has_flag(args, "--force");
+    let record_dir = get_arg(args, "--record");
+    let replay_dir = get_arg(args, "--replay");
+    let report_path = get_arg(args, "--report");
+    // A replayed bundle must not advance deletion countdowns or rewrite the
+    // real index, so it only previews like --dry-run.
+    let preview = dry_run || replay_dir.is_some();
     let index_dir = Path::new("AllayHubIndex");
 
     if !index_dir.exists() {
@@ -131,6 +308,27 @@ fn cmd_update(args: &[String]) {
         process::exit(1);
     }
 
+    let tombstones = match Tombstones::load(index_dir) {
+        Ok(tombstones) => tombstones,
+        Err(e) => {
+            error!(error = %e, "Failed to load tombstones");
+            process::exit(1);
+        }
+    };
+    let defaults = DeletionPolicy::default();
+    let options = UpdateOptions {
+        force,
+        ignored_fields: get_arg(args, "--ignore-fields")
+            .map(|f| f.split(',').map(|s| s.trim().to_string()).collect())
+            .unwrap_or_default(),
+        revisions: client().revisions(),
+        tombstones,
+        deletion: DeletionPolicy {
+            runs: parse_arg(args, "--delete-after-runs").unwrap_or(defaults.runs),
+            days: parse_arg(args, "--delete-after-days").unwrap_or(defaults.days),
+        },
+        max_deletions: parse_arg(args, "--max-deletions"),
+    };
 
     let plugins = {
         let _span = info_span!("load_plugins").entered();
@@ -138,7 +336,15 @@ fn cmd_update(args: &[String]) {
     };
     info!(count = plugins.len(), "Loaded plugins");
 
-    let (remaining, mut processed_ids) = if force {
+    let replay_session = replay_dir.as_deref().and_then(read_record_session);
+
+    let (remaining, mut processed_ids) = if let Some(session) = &replay_session {
+        info!(
+            count = session.plugins.len(),
+            "Replaying recorded plugins"
+        );
+        (session.plugins.clone(), HashSet::new())
+    } else if force {
         info!("Force mode: updating all plugins");
         clear_processed_ids();
         (plugins.clone(), HashSet::new())
@@ -155,16 +361,44 @@ fn cmd_update(args: &[String]) {
     if remaining.is_empty() {
         info!("All plugins already updated today");
         clear_processed_ids();
+        if let Some(path) = &report_path {
+            write_report(path, &RunReport::new("update", preview));
+        }
         return;
     }
     info!(count = remaining.len(), "Plugins to update");
 
+    if let Some(dir) = &record_dir {
+        write_record_session(
+            dir,
+            &RecordSession {
+                command: "update".to_string(),
+                plugins: remaining.clone(),
+                ..Default::default()
+            },
+        );
+    }
+
     let update = {
         let _span = info_span!("update_plugins", count = remaining.len()).entered();
-        update_existing_plugins(&remaining, force)
+        update_existing_plugins(&remaining, &options)
     };
 
-    if dry_run {
+    for id in &update.missing {
+        let tombstone = update.tombstones.get(id);
+        warn!(
+            id = %id,
+            reason = ?tombstone.map(|t| t.reason),
+            since = tombstone.map_or("", |t| t.first_seen.as_str()),
+            runs = tombstone.map_or(0, |t| t.confirmations),
+            "Plugin missing, not deleted yet"
+        );
+    }
+
+    if preview {
+        if replay_dir.is_some() {
+            debug!("Replay mode: index, tombstones and update progress not saved");
+        }
         if update.deleted.is_empty() {
             info!("No invalid plugins found");
         } else {
@@ -183,11 +417,11 @@ fn cmd_update(args: &[String]) {
             }
         }
     } else {
+        let mut stats = open_stats(args, index_dir, &plugins);
+
         for plugin in &update.updated {
             debug!(id = %plugin.id, "Updated");
-            if let Err(e) = write_plugin(plugin, index_dir) {
-                error!(id = %plugin.id, error = %e, "Failed to write plugin");
-            }
+            save_plugin(plugin, index_dir, stats.as_mut());
         }
 
         for id in &update.deleted {
@@ -195,9 +429,17 @@ fn cmd_update(args: &[String]) {
             if let Err(e) = delete_plugin(id, index_dir) {
                 error!(id = %id, error = %e, "Failed to delete plugin");
             }
+            if let Some(stats) = &mut stats {
+                stats.remove(id);
+            }
         }
+        save_stats(stats.as_ref(), index_dir);
+        if let Err(e) = update.tombstones.save(index_dir) {
+            error!(error = %e, "Failed to save tombstones");
+        }
+        client().record_revisions(update.revisions.clone());
 
-        processed_ids.extend(update.processed_ids);
+        processed_ids.extend(update.processed_ids.iter().cloned());
 
         if update.stopped_by_rate_limit {
             write_processed_ids(&processed_ids);
@@ -212,8 +454,9 @@ fn cmd_update(args: &[String]) {
     }
 
     info!(
-        mode = if dry_run { "preview" } else { "complete" },
+        mode = if preview { "preview" } else { "complete" },
         removed = update.deleted.len(),
+        missing = update.missing.len(),
         updated = update.updated.len(),
         unchanged = update.unchanged.len(),
         api_calls = client().api_calls(),
@@ -222,7 +465,14 @@ fn cmd_update(args: &[String]) {
         "Update finished"
     );
 
-    client().export_data_cache().save();
+    if let Some(path) = &report_path {
+        write_report(path, &RunReport::from_update(&update, preview));
+    }
+
+    // A recording ran on an empty cache; saving it would drop the real one.
+    if replay_dir.is_none() && record_dir.is_none() {
+        save_data_cache();
+    }
 }
 
 fn cmd_discover(args: &[String]) {
@@ -234,6 +484,9 @@ fn cmd_discover(args: &[String]) {
     }
 
     let dry_run = has_flag(args, "--dry-run");
+    let record_dir = get_arg(args, "--record");
+    let replay_dir = get_arg(args, "--replay");
+    let report_path = get_arg(args, "--report");
     let index_dir = Path::new("AllayHubIndex");
 
     if !index_dir.exists() {
@@ -241,7 +494,6 @@ fn cmd_discover(args: &[String]) {
         process::exit(1);
     }
 
-
     let plugins = {
         let _span = info_span!("load_plugins").entered();
         load_plugins(index_dir)
@@ -254,7 +506,9 @@ fn cmd_discover(args: &[String]) {
         .filter_map(|p| extract_repo_full_name(&p.source))
         .collect();
 
-    let last_sync = if has_flag(args, "--force") {
+    let last_sync = if let Some(session) = replay_dir.as_deref().and_then(read_record_session) {
+        session.last_sync
+    } else if has_flag(args, "--force") {
         info!("Full scan mode");
         None
     } else {
@@ -264,12 +518,27 @@ fn cmd_discover(args: &[String]) {
         info!(since = %date, "Incremental scan");
     }
 
+    if let Some(dir) = &record_dir {
+        write_record_session(
+            dir,
+            &RecordSession {
+                command: "discover".to_string(),
+                last_sync: last_sync.clone(),
+                ..Default::default()
+            },
+        );
+    }
+
     let discover = {
         let _span = info_span!("discover_plugins").entered();
         discover_new_plugins(&existing_ids, &existing_repos, last_sync.as_deref())
     };
 
-    if dry_run {
+    let preview = dry_run || replay_dir.is_some();
+    if preview {
+        if replay_dir.is_some() {
+            debug!("Replay mode: index and last sync not saved");
+        }
         if discover.new_plugins.is_empty() {
             info!("No new plugins found");
         } else {
@@ -279,12 +548,12 @@ fn cmd_discover(args: &[String]) {
             }
         }
     } else {
+        let mut stats = open_stats(args, index_dir, &plugins);
         for plugin in &discover.new_plugins {
             debug!(name = %plugin.name, id = %plugin.id, "New plugin");
-            if let Err(e) = write_plugin(plugin, index_dir) {
-                error!(id = %plugin.id, error = %e, "Failed to write plugin");
-            }
+            save_plugin(plugin, index_dir, stats.as_mut());
         }
+        save_stats(stats.as_ref(), index_dir);
         write_last_sync();
     }
 
@@ -293,7 +562,7 @@ fn cmd_discover(args: &[String]) {
     }
 
     info!(
-        mode = if dry_run { "preview" } else { "complete" },
+        mode = if preview { "preview" } else { "complete" },
         found = discover.new_plugins.len(),
         api_calls = client().api_calls(),
         cache_hits = client().cache_hits(),
@@ -301,5 +570,183 @@ fn cmd_discover(args: &[String]) {
         "Discover finished"
     );
 
-    client().export_data_cache().save();
+    if let Some(path) = &report_path {
+        write_report(path, &RunReport::from_discover(&discover, preview));
+    }
+
+    // A recording ran on an empty cache; saving it would drop the real one.
+    if replay_dir.is_none() && record_dir.is_none() {
+        save_data_cache();
+    }
+}
+
+/// Persist the client's cache, dropping entries no run has used lately.
+fn save_data_cache() {
+    let mut cache = client().export_data_cache();
+    let removed = cache.prune(&PruneOptions {
+        max_age_days: Some(DEFAULT_MAX_AGE_DAYS),
+        referenced: None,
+    });
+    if removed.total() > 0 {
+        info!(removed = removed.total(), "Pruned stale cache entries");
+    }
+    cache.save();
+}
+
+/// Output of `cache stats`.
+#[derive(Serialize)]
+struct CacheStatsReport {
+    version: u32,
+    data_file_bytes: u64,
+    blob_file_bytes: u64,
+    entries: CacheCounts,
+    /// Entries `update` would prune for not being used in
+    /// `DEFAULT_MAX_AGE_DAYS`.
+    stale: CacheCounts,
+}
+
+fn cmd_cache(args: &[String]) {
+    let _span = info_span!("cache").entered();
+
+    match args.first().map(String::as_str) {
+        Some("stats") => {
+            let cache = DataCache::load();
+            let stale = cache.clone().prune(&PruneOptions {
+                max_age_days: Some(DEFAULT_MAX_AGE_DAYS),
+                referenced: None,
+            });
+            let report = CacheStatsReport {
+                version: CACHE_VERSION,
+                data_file_bytes: file_size(CACHE_FILE),
+                blob_file_bytes: file_size(BLOB_CACHE_FILE),
+                entries: cache.counts(),
+                stale,
+            };
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => println!("{}", json),
+                Err(e) => error!(error = %e, "Failed to serialize report"),
+            }
+        }
+        Some("prune") => {
+            let max_age_days = match get_arg(args, "--max-age-days").map(|d| d.parse()) {
+                None => DEFAULT_MAX_AGE_DAYS,
+                Some(Ok(days)) => days,
+                Some(Err(_)) => {
+                    error!("--max-age-days expects a number of days");
+                    process::exit(1);
+                }
+            };
+            let referenced = if has_flag(args, "--keep-unreferenced") {
+                None
+            } else {
+                referenced_keys(Path::new("AllayHubIndex"))
+            };
+
+            let mut cache = DataCache::load();
+            let removed = cache.prune(&PruneOptions {
+                max_age_days: Some(max_age_days),
+                referenced,
+            });
+            cache.save();
+            info!(
+                repositories = removed.repositories,
+                trees = removed.trees,
+                revisions = removed.revisions,
+                responses = removed.responses,
+                blobs = removed.blobs,
+                "Cache pruned"
+            );
+        }
+        Some("clear") => {
+            DataCache::clear();
+            info!("Cache cleared");
+        }
+        _ => {
+            error!("cache requires stats, prune or clear");
+            print_usage();
+            process::exit(1);
+        }
+    }
+}
+
+/// Lowercase repository names and plugin ids of the index, or `None` when
+/// there is no index to compare against.
+fn referenced_keys(index_dir: &Path) -> Option<HashSet<String>> {
+    if !index_dir.exists() {
+        warn!(path = ?index_dir, "Index directory not found, keeping unreferenced entries");
+        return None;
+    }
+    let mut keys = HashSet::new();
+    for plugin in load_plugins(index_dir) {
+        if let Some(repo) = extract_repo_full_name(&plugin.source) {
+            keys.insert(repo.to_lowercase());
+        }
+        keys.insert(plugin.id.to_lowercase());
+    }
+    Some(keys)
+}
+
+fn write_report(path: &str, report: &RunReport) {
+    match report.write(Path::new(path)) {
+        Ok(()) => info!(path = %path, "Wrote run report"),
+        Err(e) => error!(error = %e, "Failed to write run report"),
+    }
+}
+
+/// Value of a numeric flag; exits when it doesn't parse.
+fn parse_arg<T: std::str::FromStr>(args: &[String], flag: &str) -> Option<T> {
+    let value = get_arg(args, flag)?;
+    match value.parse() {
+        Ok(v) => Some(v),
+        Err(_) => {
+            error!(flag = flag, value = %value, "Expected a number");
+            process::exit(1);
+        }
+    }
+}
+
+/// The index keeps its counters in a stats file once it has one. With
+/// `--split-stats` the file is started by moving the counters of every
+/// indexed plugin into it.
+fn open_stats(args: &[String], index_dir: &Path, plugins: &[Plugin]) -> Option<IndexStats> {
+    if IndexStats::exists(index_dir) {
+        return match IndexStats::load(index_dir) {
+            Ok(stats) => Some(stats),
+            Err(e) => {
+                error!(error = %e, "Failed to load stats");
+                process::exit(1);
+            }
+        };
+    }
+    if !has_flag(args, "--split-stats") {
+        return None;
+    }
+
+    info!(count = plugins.len(), "Moving counters to the stats file");
+    let mut stats = IndexStats::default();
+    for plugin in plugins {
+        save_plugin(plugin, index_dir, Some(&mut stats));
+    }
+    Some(stats)
+}
+
+fn save_plugin(plugin: &Plugin, index_dir: &Path, stats: Option<&mut IndexStats>) {
+    let result = match stats {
+        Some(stats) => {
+            stats.record(plugin);
+            write_plugin_metadata(plugin, index_dir)
+        }
+        None => write_plugin(plugin, index_dir),
+    };
+    if let Err(e) = result {
+        error!(id = %plugin.id, error = %e, "Failed to write plugin");
+    }
+}
+
+fn save_stats(stats: Option<&IndexStats>, index_dir: &Path) {
+    if let Some(stats) = stats
+        && let Err(e) = stats.save(index_dir)
+    {
+        error!(error = %e, "Failed to write stats");
+    }
 }
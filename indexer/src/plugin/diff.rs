@@ -0,0 +1,186 @@
+use super::types::Plugin;
+use serde::Serialize;
+use serde_json::Value;
+
+/// A changed leaf of a plugin, addressed by a path such as `stars`,
+/// `license.id` or `versions[1.2.0].downloads`. Added and removed entries
+/// have `null` on the missing side.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl FieldChange {
+    /// The top-level `Plugin` field this change belongs to.
+    pub fn field(&self) -> &str {
+        let end = self.path.find(['.', '[']).unwrap_or(self.path.len());
+        &self.path[..end]
+    }
+}
+
+/// Keys identifying the elements of object arrays, so a new release at the
+/// top of `versions` shows up as one added entry rather than every entry
+/// shifting by one.
+const ELEMENT_KEYS: &[&str] = &["version", "filename", "url", "plugin_id", "name"];
+
+/// Structural diff between two plugins as written to the index.
+pub fn diff_plugins(old: &Plugin, new: &Plugin) -> Vec<FieldChange> {
+    let old = serde_json::to_value(old).unwrap_or(Value::Null);
+    let new = serde_json::to_value(new).unwrap_or(Value::Null);
+    let mut changes = Vec::new();
+    diff_values("", &old, &new, &mut changes);
+    changes
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, out: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(o), Value::Object(n)) => {
+            for (key, old_value) in o {
+                let child = join_key(path, key);
+                diff_values(&child, old_value, n.get(key).unwrap_or(&Value::Null), out);
+            }
+            for (key, new_value) in n {
+                if !o.contains_key(key) {
+                    diff_values(&join_key(path, key), &Value::Null, new_value, out);
+                }
+            }
+        }
+        (Value::Array(o), Value::Array(n)) => match element_key(o, n) {
+            Some(key) => diff_keyed(path, key, o, n, out),
+            None => {
+                for i in 0..o.len().max(n.len()) {
+                    diff_values(
+                        &format!("{}[{}]", path, i),
+                        o.get(i).unwrap_or(&Value::Null),
+                        n.get(i).unwrap_or(&Value::Null),
+                        out,
+                    );
+                }
+            }
+        },
+        _ if old != new => out.push(FieldChange {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn diff_keyed(path: &str, key: &str, old: &[Value], new: &[Value], out: &mut Vec<FieldChange>) {
+    let id = |v: &Value| v.get(key).map(value_label).unwrap_or_default();
+
+    for o in old {
+        let label = id(o);
+        let n = new.iter().find(|n| id(n) == label).unwrap_or(&Value::Null);
+        diff_values(&format!("{}[{}]", path, label), o, n, out);
+    }
+    for n in new {
+        let label = id(n);
+        if !old.iter().any(|o| id(o) == label) {
+            out.push(FieldChange {
+                path: format!("{}[{}]", path, label),
+                old: Value::Null,
+                new: n.clone(),
+            });
+        }
+    }
+}
+
+/// The first of `ELEMENT_KEYS` that every element of both arrays has, with
+/// unique values.
+fn element_key(old: &[Value], new: &[Value]) -> Option<&'static str> {
+    if old.is_empty() && new.is_empty() {
+        return None;
+    }
+    ELEMENT_KEYS.iter().copied().find(|key| {
+        [old, new].iter().all(|items| {
+            let labels: Vec<String> = items
+                .iter()
+                .filter_map(|v| v.get(*key).map(value_label))
+                .collect();
+            labels.len() == items.len()
+                && labels
+                    .iter()
+                    .enumerate()
+                    .all(|(i, l)| !labels[..i].contains(l))
+        })
+    })
+}
+
+fn value_label(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn diff(old: Value, new: Value) -> Vec<(String, Value, Value)> {
+        let mut changes = Vec::new();
+        diff_values("", &old, &new, &mut changes);
+        changes
+            .into_iter()
+            .map(|c| (c.path, c.old, c.new))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_nested_fields() {
+        let changes = diff(
+            json!({"stars": 1, "license": {"id": "MIT", "url": ""}, "tags": ["a"]}),
+            json!({"stars": 2, "license": {"id": "MIT", "url": "https://x"}, "tags": ["a", "b"]}),
+        );
+        assert_eq!(
+            changes,
+            vec![
+                ("license.url".to_string(), json!(""), json!("https://x")),
+                ("stars".to_string(), json!(1), json!(2)),
+                ("tags[1]".to_string(), Value::Null, json!("b")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_keyed_arrays() {
+        let changes = diff(
+            json!({"versions": [{"version": "1.0", "downloads": 5}]}),
+            json!({"versions": [{"version": "1.1", "downloads": 0}, {"version": "1.0", "downloads": 7}]}),
+        );
+        assert_eq!(
+            changes,
+            vec![
+                ("versions[1.0].downloads".to_string(), json!(5), json!(7)),
+                (
+                    "versions[1.1]".to_string(),
+                    Value::Null,
+                    json!({"version": "1.1", "downloads": 0})
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_field_change_field() {
+        let change = FieldChange {
+            path: "versions[1.0].downloads".to_string(),
+            old: Value::Null,
+            new: Value::Null,
+        };
+        assert_eq!(change.field(), "versions");
+    }
+}
@@ -1,16 +1,26 @@
+use super::stats::IndexStats;
+use super::tombstones::Tombstones;
 use super::types::Plugin;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::{error, warn};
 
+/// Load every plugin of the index, with counters from the stats file merged
+/// back in when the index keeps them separately.
 pub fn load_plugins(index_dir: &Path) -> Vec<Plugin> {
     let mut plugins = Vec::new();
-    load_plugins_recursive(index_dir, &mut plugins);
+    let skipped = [IndexStats::path(index_dir), Tombstones::path(index_dir)];
+    load_plugins_recursive(index_dir, &skipped, &mut plugins);
+
+    match IndexStats::load(index_dir) {
+        Ok(stats) => plugins.iter_mut().for_each(|p| stats.apply(p)),
+        Err(e) => error!(error = %e, "Failed to load stats"),
+    }
     plugins
 }
 
-fn load_plugins_recursive(dir: &Path, plugins: &mut Vec<Plugin>) {
+fn load_plugins_recursive(dir: &Path, skipped: &[PathBuf], plugins: &mut Vec<Plugin>) {
     let entries = match fs::read_dir(dir) {
         Ok(e) => e,
         Err(e) => {
@@ -22,7 +32,9 @@ fn load_plugins_recursive(dir: &Path, plugins: &mut Vec<Plugin>) {
     for entry in entries.flatten() {
         let path = entry.path();
         if path.is_dir() {
-            load_plugins_recursive(&path, plugins);
+            load_plugins_recursive(&path, skipped, plugins);
+        } else if skipped.contains(&path) {
+            continue;
         } else if path.extension().is_some_and(|e| e == "json")
             && let Ok(content) = fs::read_to_string(&path) {
                 match parse_plugin_with_preserved_fields(&content) {
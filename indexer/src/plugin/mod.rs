@@ -1,7 +1,15 @@
+pub mod diff;
 pub mod loader;
+pub mod stats;
+pub mod tombstones;
 pub mod types;
+pub mod validate;
 pub mod writer;
 
+pub use diff::{FieldChange, diff_plugins};
 pub use loader::load_plugins;
+pub use stats::{IndexStats, PluginStats};
+pub use tombstones::{DeletionPolicy, MissingReason, Tombstone, Tombstones};
 pub use types::*;
-pub use writer::{delete_plugin, write_plugin};
+pub use validate::{ValidationReport, validate_index};
+pub use writer::{delete_plugin, write_plugin, write_plugin_metadata};
@@ -0,0 +1,163 @@
+use super::types::Plugin;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Aggregated counters of every plugin, stored next to the `{owner}` folders
+/// of the index.
+pub const STATS_FILE: &str = "stats.json";
+
+/// Counters that change on nearly every run. Keeping them out of the plugin
+/// files keeps the history of the metadata readable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PluginStats {
+    #[serde(default)]
+    pub stars: u64,
+    #[serde(default)]
+    pub downloads: u64,
+    /// Downloads per version string.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub versions: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IndexStats {
+    plugins: BTreeMap<String, PluginStats>,
+}
+
+impl IndexStats {
+    pub fn path(index_dir: &Path) -> PathBuf {
+        index_dir.join(STATS_FILE)
+    }
+
+    /// Whether the index stores its counters separately.
+    pub fn exists(index_dir: &Path) -> bool {
+        Self::path(index_dir).is_file()
+    }
+
+    pub fn load(index_dir: &Path) -> Result<Self, String> {
+        let path = Self::path(index_dir);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse {:?}: {}", path, e))
+    }
+
+    pub fn save(&self, index_dir: &Path) -> Result<(), String> {
+        let path = Self::path(index_dir);
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+
+    pub fn get(&self, plugin_id: &str) -> Option<&PluginStats> {
+        self.plugins.get(plugin_id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.plugins.keys().map(String::as_str)
+    }
+
+    /// Take over the counters of `plugin`. Counters kept in the plugin file
+    /// with a `!` prefix stay there and are not recorded.
+    pub fn record(&mut self, plugin: &Plugin) {
+        let preserved = |field: &str| plugin.preserved_fields.contains_key(field);
+        let stats = PluginStats {
+            stars: if preserved("stars") { 0 } else { plugin.stars },
+            downloads: if preserved("downloads") {
+                0
+            } else {
+                plugin.downloads
+            },
+            versions: if preserved("versions") {
+                BTreeMap::new()
+            } else {
+                plugin
+                    .versions
+                    .iter()
+                    .filter(|v| v.downloads > 0)
+                    .map(|v| (v.version.clone(), v.downloads))
+                    .collect()
+            },
+        };
+        self.plugins.insert(plugin.id.clone(), stats);
+    }
+
+    pub fn remove(&mut self, plugin_id: &str) {
+        self.plugins.remove(plugin_id);
+    }
+
+    /// Fill the counters of a plugin loaded from a metadata-only file.
+    pub fn apply(&self, plugin: &mut Plugin) {
+        let Some(stats) = self.plugins.get(&plugin.id) else {
+            return;
+        };
+        let preserved = |field: &str| plugin.preserved_fields.contains_key(field);
+        if !preserved("stars") {
+            plugin.stars = stats.stars;
+        }
+        if !preserved("downloads") {
+            plugin.downloads = stats.downloads;
+        }
+        if !preserved("versions") {
+            for version in &mut plugin.versions {
+                if let Some(&downloads) = stats.versions.get(&version.version) {
+                    version.downloads = downloads;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin() -> Plugin {
+        serde_json::from_value(serde_json::json!({
+            "id": "example/hello",
+            "name": "Hello",
+            "source": "https://github.com/example/hello",
+            "stars": 12,
+            "downloads": 42,
+            "versions": [{"version": "1.1.0", "downloads": 40}, {"version": "1.0.0", "downloads": 2}]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_record_and_apply() {
+        let mut stats = IndexStats::default();
+        stats.record(&plugin());
+
+        let mut stripped = plugin();
+        stripped.stars = 0;
+        stripped.downloads = 0;
+        stripped.versions.iter_mut().for_each(|v| v.downloads = 0);
+        stats.apply(&mut stripped);
+
+        assert_eq!(stripped.stars, 12);
+        assert_eq!(stripped.downloads, 42);
+        assert_eq!(stripped.versions[0].downloads, 40);
+        assert_eq!(stripped.versions[1].downloads, 2);
+    }
+
+    #[test]
+    fn test_preserved_counters_stay_in_plugin_file() {
+        let mut plugin = plugin();
+        plugin
+            .preserved_fields
+            .insert("stars".to_string(), serde_json::json!(99));
+        plugin.stars = 99;
+
+        let mut stats = IndexStats::default();
+        stats.record(&plugin);
+        assert_eq!(stats.get("example/hello").unwrap().stars, 0);
+
+        stats.apply(&mut plugin);
+        assert_eq!(plugin.stars, 99);
+    }
+}
@@ -0,0 +1,195 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Plugins found missing by `update` but not deleted yet, stored next to the
+/// stats file of the index.
+pub const TOMBSTONES_FILE: &str = "tombstones.json";
+
+pub const DEFAULT_DELETE_AFTER_RUNS: u32 = 3;
+pub const DEFAULT_DELETE_AFTER_DAYS: i64 = 7;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Why `update` considers a plugin gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingReason {
+    /// The repository returns 404.
+    NotFound,
+    Archived,
+    /// The repository has the `noindex` topic.
+    NoIndex,
+    /// The repository no longer declares a plugin with this id.
+    ModuleRemoved,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub reason: MissingReason,
+    /// `YYYY-MM-DD` of the first run that found the plugin missing.
+    pub first_seen: String,
+    /// `YYYY-MM-DD` of the latest run that found it missing.
+    #[serde(default)]
+    pub last_seen: String,
+    /// Distinct days on which consecutive runs found it missing. Runs are
+    /// hourly, so counting every run would let a few hours of GitHub
+    /// trouble delete a listing.
+    pub confirmations: u32,
+}
+
+/// When a missing plugin is deleted: once runs on `runs` distinct days found
+/// it missing, or `days` after the first one did, whichever comes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeletionPolicy {
+    pub runs: u32,
+    pub days: i64,
+}
+
+impl Default for DeletionPolicy {
+    fn default() -> Self {
+        Self {
+            runs: DEFAULT_DELETE_AFTER_RUNS,
+            days: DEFAULT_DELETE_AFTER_DAYS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Tombstones {
+    plugins: BTreeMap<String, Tombstone>,
+}
+
+impl Tombstones {
+    pub fn path(index_dir: &Path) -> PathBuf {
+        index_dir.join(TOMBSTONES_FILE)
+    }
+
+    pub fn load(index_dir: &Path) -> Result<Self, String> {
+        let path = Self::path(index_dir);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse {:?}: {}", path, e))
+    }
+
+    /// Write the tombstones, or remove the file when there are none.
+    pub fn save(&self, index_dir: &Path) -> Result<(), String> {
+        let path = Self::path(index_dir);
+        if self.plugins.is_empty() {
+            if path.is_file() {
+                fs::remove_file(&path).map_err(|e| format!("Failed to remove {:?}: {}", path, e))?;
+            }
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+
+    pub fn get(&self, plugin_id: &str) -> Option<&Tombstone> {
+        self.plugins.get(plugin_id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.plugins.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Record that a run on `today` found `plugin_id` missing, counting at
+    /// most one confirmation per day. Returns whether `policy` now allows
+    /// deleting it.
+    pub fn mark(
+        &mut self,
+        plugin_id: &str,
+        reason: MissingReason,
+        today: NaiveDate,
+        policy: &DeletionPolicy,
+    ) -> bool {
+        let tombstone = self
+            .plugins
+            .entry(plugin_id.to_string())
+            .or_insert_with(|| Tombstone {
+                reason,
+                first_seen: today.format(DATE_FORMAT).to_string(),
+                last_seen: String::new(),
+                confirmations: 0,
+            });
+        tombstone.reason = reason;
+        let today_str = today.format(DATE_FORMAT).to_string();
+        if tombstone.last_seen != today_str {
+            tombstone.confirmations += 1;
+            tombstone.last_seen = today_str;
+        }
+
+        let days = NaiveDate::parse_from_str(&tombstone.first_seen, DATE_FORMAT)
+            .map(|first| (today - first).num_days())
+            .unwrap_or(0);
+        tombstone.confirmations >= policy.runs || days >= policy.days
+    }
+
+    /// Forget `plugin_id`, e.g. because it was found again or deleted.
+    /// Returns whether it had a tombstone.
+    pub fn remove(&mut self, plugin_id: &str) -> bool {
+        self.plugins.remove(plugin_id).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+    }
+
+    #[test]
+    fn test_deleted_after_runs_on_distinct_days() {
+        let policy = DeletionPolicy { runs: 3, days: 30 };
+        let mut tombstones = Tombstones::default();
+
+        assert!(!tombstones.mark("a/b", MissingReason::NotFound, day(1), &policy));
+        assert!(!tombstones.mark("a/b", MissingReason::NotFound, day(1), &policy));
+        assert!(!tombstones.mark("a/b", MissingReason::NotFound, day(1), &policy));
+        assert!(!tombstones.mark("a/b", MissingReason::NotFound, day(2), &policy));
+        assert!(tombstones.mark("a/b", MissingReason::Archived, day(3), &policy));
+
+        let tombstone = tombstones.get("a/b").unwrap();
+        assert_eq!(tombstone.first_seen, "2025-01-01");
+        assert_eq!(tombstone.last_seen, "2025-01-03");
+        assert_eq!(tombstone.confirmations, 3);
+        assert_eq!(tombstone.reason, MissingReason::Archived);
+    }
+
+    #[test]
+    fn test_deleted_after_grace_days() {
+        let policy = DeletionPolicy { runs: 10, days: 7 };
+        let mut tombstones = Tombstones::default();
+
+        assert!(!tombstones.mark("a/b", MissingReason::NoIndex, day(1), &policy));
+        assert!(!tombstones.mark("a/b", MissingReason::NoIndex, day(7), &policy));
+        assert!(tombstones.mark("a/b", MissingReason::NoIndex, day(8), &policy));
+    }
+
+    #[test]
+    fn test_found_again_resets() {
+        let policy = DeletionPolicy { runs: 2, days: 30 };
+        let mut tombstones = Tombstones::default();
+
+        tombstones.mark("a/b", MissingReason::NotFound, day(1), &policy);
+        assert!(tombstones.remove("a/b"));
+        assert!(!tombstones.mark("a/b", MissingReason::NotFound, day(2), &policy));
+        assert_eq!(tombstones.get("a/b").unwrap().confirmations, 1);
+    }
+}
@@ -37,6 +37,9 @@ pub struct Plugin {
     pub api_version: String,
     #[serde(default)]
     pub server_version: String,
+    /// AllayGradle plugin version the build applies, if known.
+    #[serde(default)]
+    pub allay_gradle_version: String,
     #[serde(default)]
     pub dependencies: Vec<Dependency>,
     #[serde(default, skip_serializing)]
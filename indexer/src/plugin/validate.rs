@@ -0,0 +1,403 @@
+use super::loader::load_plugin;
+use super::stats::{IndexStats, STATS_FILE};
+use super::tombstones::{TOMBSTONES_FILE, Tombstones};
+use super::types::Plugin;
+use crate::sync::builder::CATEGORIES;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationIssue {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_id: Option<String>,
+    pub severity: Severity,
+    pub check: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationReport {
+    pub files: usize,
+    pub plugins: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors == 0
+    }
+
+    fn push(
+        &mut self,
+        path: &str,
+        plugin_id: Option<&str>,
+        severity: Severity,
+        check: &'static str,
+        message: String,
+    ) {
+        match severity {
+            Severity::Error => self.errors += 1,
+            Severity::Warning => self.warnings += 1,
+        }
+        self.issues.push(ValidationIssue {
+            path: path.to_string(),
+            plugin_id: plugin_id.map(str::to_string),
+            severity,
+            check,
+            message,
+        });
+    }
+}
+
+/// Lint every plugin file under `index_dir`.
+///
+/// Files that fail to parse, ids that don't match their `owner/name.json`
+/// path, unknown categories, malformed URLs and `!`-preserved keys that are
+/// not `Plugin` fields are errors. Dependencies that resolve to no indexed
+/// plugin are warnings, since plugins may depend on ones outside AllayHub,
+/// unless `strict_dependencies` makes them errors.
+pub fn validate_index(index_dir: &Path, strict_dependencies: bool) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let mut files = Vec::new();
+    collect_json_files(index_dir, &mut files, &mut report);
+    files.retain(|f| *f != IndexStats::path(index_dir) && *f != Tombstones::path(index_dir));
+    files.sort();
+    report.files = files.len();
+
+    let mut plugins = Vec::new();
+    for file in &files {
+        let path = display_path(index_dir, file);
+        match load_plugin(file) {
+            Ok(plugin) => plugins.push((path, plugin)),
+            Err(e) => report.push(&path, None, Severity::Error, "parse", e),
+        }
+    }
+    report.plugins = plugins.len();
+
+    let ids: Vec<&str> = plugins.iter().map(|(_, p)| p.id.as_str()).collect();
+    for (path, plugin) in &plugins {
+        check_id(path, plugin, &mut report);
+        check_categories(path, plugin, &mut report);
+        check_dependencies(path, plugin, &ids, strict_dependencies, &mut report);
+        check_urls(path, plugin, &mut report);
+        check_preserved_fields(path, plugin, &mut report);
+    }
+    check_stats(index_dir, &ids, &mut report);
+    check_tombstones(index_dir, &ids, &mut report);
+
+    report
+}
+
+fn collect_json_files(dir: &Path, files: &mut Vec<PathBuf>, report: &mut ValidationReport) {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) => {
+            let path = dir.to_string_lossy();
+            report.push(&path, None, Severity::Error, "read", e.to_string());
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_json_files(&path, files, report);
+        } else if path.extension().is_some_and(|e| e == "json") {
+            files.push(path);
+        }
+    }
+}
+
+/// `owner/name.json`, relative to the index directory and `/`-separated.
+fn display_path(index_dir: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(index_dir).unwrap_or(file);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn check_id(path: &str, plugin: &Plugin, report: &mut ValidationReport) {
+    let id = plugin.id.as_str();
+    let segments: Vec<&str> = id.split('/').collect();
+    if segments.len() != 2 || segments.iter().any(|s| s.is_empty()) {
+        report.push(
+            path,
+            Some(id),
+            Severity::Error,
+            "id",
+            format!("id {:?} is not in owner/name form", id),
+        );
+        return;
+    }
+    if id != id.to_lowercase() {
+        report.push(
+            path,
+            Some(id),
+            Severity::Error,
+            "id",
+            format!("id {:?} must be lowercase", id),
+        );
+    }
+    let expected = format!("{}.json", id);
+    if path != expected {
+        report.push(
+            path,
+            Some(id),
+            Severity::Error,
+            "id",
+            format!("id {:?} should be stored at {}", id, expected),
+        );
+    }
+}
+
+fn check_categories(path: &str, plugin: &Plugin, report: &mut ValidationReport) {
+    for category in &plugin.categories {
+        if !CATEGORIES.contains(&category.as_str()) {
+            report.push(
+                path,
+                Some(&plugin.id),
+                Severity::Error,
+                "category",
+                format!("unknown category {:?}", category),
+            );
+        }
+    }
+}
+
+/// Dependency ids are either full `owner/name` ids or bare names, which the
+/// site resolves against the name part of every indexed id.
+fn check_dependencies(
+    path: &str,
+    plugin: &Plugin,
+    ids: &[&str],
+    strict: bool,
+    report: &mut ValidationReport,
+) {
+    let severity = if strict { Severity::Error } else { Severity::Warning };
+    for dep in &plugin.dependencies {
+        let target = dep.plugin_id.to_lowercase();
+        let resolved = if target.contains('/') {
+            ids.iter().any(|id| id.to_lowercase() == target)
+        } else {
+            ids.iter().any(|id| {
+                id.rsplit('/')
+                    .next()
+                    .is_some_and(|name| name.to_lowercase() == target)
+            })
+        };
+        if !resolved {
+            report.push(
+                path,
+                Some(&plugin.id),
+                severity,
+                "dependency",
+                format!("dependency {:?} is not an indexed plugin", dep.plugin_id),
+            );
+        }
+    }
+}
+
+fn check_urls(path: &str, plugin: &Plugin, report: &mut ValidationReport) {
+    let mut urls: Vec<(String, &str)> = vec![
+        ("source".to_string(), plugin.source.as_str()),
+        ("icon_url".to_string(), plugin.icon_url.as_str()),
+        ("license.url".to_string(), plugin.license.url.as_str()),
+    ];
+    if let Some(links) = &plugin.links {
+        urls.push(("links.homepage".to_string(), links.homepage.as_str()));
+        urls.push(("links.wiki".to_string(), links.wiki.as_str()));
+        urls.push(("links.discord".to_string(), links.discord.as_str()));
+    }
+    for (i, author) in plugin.authors.iter().enumerate() {
+        urls.push((format!("authors[{}].url", i), author.url.as_str()));
+        urls.push((
+            format!("authors[{}].avatar_url", i),
+            author.avatar_url.as_str(),
+        ));
+    }
+    for (i, item) in plugin.gallery.iter().enumerate() {
+        urls.push((format!("gallery[{}].url", i), item.url.as_str()));
+    }
+    for version in &plugin.versions {
+        for (i, file) in version.files.iter().enumerate() {
+            urls.push((
+                format!("versions[{}].files[{}].url", version.version, i),
+                file.url.as_str(),
+            ));
+        }
+    }
+
+    if plugin.source.is_empty() {
+        report.push(
+            path,
+            Some(&plugin.id),
+            Severity::Error,
+            "url",
+            "source is empty".to_string(),
+        );
+    }
+
+    for (field, url) in urls {
+        if !url.is_empty() && !is_valid_url(url) {
+            report.push(
+                path,
+                Some(&plugin.id),
+                Severity::Error,
+                "url",
+                format!("{} is not a valid URL: {:?}", field, url),
+            );
+        }
+    }
+}
+
+/// An absolute `http(s)` URL with a host and no whitespace.
+fn is_valid_url(url: &str) -> bool {
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'))
+        && !url.chars().any(char::is_whitespace)
+}
+
+fn check_preserved_fields(path: &str, plugin: &Plugin, report: &mut ValidationReport) {
+    let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(plugin) else {
+        return;
+    };
+    let mut preserved: Vec<&String> = plugin.preserved_fields.keys().collect();
+    preserved.sort();
+    for field in preserved {
+        if !fields.contains_key(field) {
+            report.push(
+                path,
+                Some(&plugin.id),
+                Severity::Error,
+                "preserved_field",
+                format!("\"!{}\" does not name a plugin field", field),
+            );
+        }
+    }
+}
+
+fn check_stats(index_dir: &Path, ids: &[&str], report: &mut ValidationReport) {
+    let stats = match IndexStats::load(index_dir) {
+        Ok(stats) => stats,
+        Err(e) => {
+            report.push(STATS_FILE, None, Severity::Error, "stats", e);
+            return;
+        }
+    };
+    for id in stats.ids() {
+        if !ids.contains(&id) {
+            report.push(
+                STATS_FILE,
+                Some(id),
+                Severity::Warning,
+                "stats",
+                format!("stats for {:?}, which is not an indexed plugin", id),
+            );
+        }
+    }
+}
+
+fn check_tombstones(index_dir: &Path, ids: &[&str], report: &mut ValidationReport) {
+    let tombstones = match Tombstones::load(index_dir) {
+        Ok(tombstones) => tombstones,
+        Err(e) => {
+            report.push(TOMBSTONES_FILE, None, Severity::Error, "tombstones", e);
+            return;
+        }
+    };
+    for id in tombstones.ids() {
+        if !ids.contains(&id) {
+            report.push(
+                TOMBSTONES_FILE,
+                Some(id),
+                Severity::Warning,
+                "tombstones",
+                format!("tombstone for {:?}, which is not an indexed plugin", id),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_url() {
+        assert!(is_valid_url("https://github.com/AllayMC/Allay"));
+        assert!(is_valid_url("http://localhost:8080/a?b#c"));
+        assert!(!is_valid_url("github.com/AllayMC/Allay"));
+        assert!(!is_valid_url("https://"));
+        assert!(!is_valid_url("https://exa mple.com"));
+    }
+
+    #[test]
+    fn test_validate_index() {
+        let dir =
+            std::env::temp_dir().join(format!("allayindexer-validate-{}", std::process::id()));
+        fs::create_dir_all(dir.join("example")).unwrap();
+        fs::write(
+            dir.join("example/good.json"),
+            r#"{"id":"example/good","name":"Good","source":"https://github.com/example/good","categories":["utility"]}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("example/bad.json"),
+            r#"{"id":"example/other","name":"Bad","source":"github.com/example/bad","categories":["misc"],"!nope":1,
+                "dependencies":[{"plugin_id":"good"},{"plugin_id":"missing"}]}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("example/broken.json"), "{").unwrap();
+
+        let report = validate_index(&dir, false);
+        let strict = validate_index(&dir, true);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(report.files, 3);
+        assert_eq!(report.plugins, 2);
+        assert!(!report.is_ok());
+        let checks: Vec<(&str, &str)> = report
+            .issues
+            .iter()
+            .map(|i| (i.path.as_str(), i.check))
+            .collect();
+        assert_eq!(
+            checks,
+            vec![
+                ("example/broken.json", "parse"),
+                ("example/bad.json", "id"),
+                ("example/bad.json", "category"),
+                ("example/bad.json", "dependency"),
+                ("example/bad.json", "url"),
+                ("example/bad.json", "preserved_field"),
+            ]
+        );
+        assert_eq!(report.errors, 5);
+        assert_eq!(report.warnings, 1);
+        assert_eq!(strict.errors, 6);
+        assert_eq!(strict.warnings, 0);
+    }
+}
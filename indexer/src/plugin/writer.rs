@@ -3,6 +3,16 @@ use std::fs;
 use std::path::Path;
 
 pub fn write_plugin(plugin: &Plugin, output_dir: &Path) -> Result<(), String> {
+    write_plugin_file(plugin, output_dir, false)
+}
+
+/// Write a plugin without its counters, which the caller records in the
+/// index's `IndexStats` instead. Counters preserved with `!` stay in the file.
+pub fn write_plugin_metadata(plugin: &Plugin, output_dir: &Path) -> Result<(), String> {
+    write_plugin_file(plugin, output_dir, true)
+}
+
+fn write_plugin_file(plugin: &Plugin, output_dir: &Path, strip_stats: bool) -> Result<(), String> {
     let path = plugin_path(output_dir, &plugin.id);
 
     if let Some(parent) = path.parent() {
@@ -13,6 +23,9 @@ pub fn write_plugin(plugin: &Plugin, output_dir: &Path) -> Result<(), String> {
     let mut value = serde_json::to_value(plugin).map_err(|e| e.to_string())?;
 
     if let Some(obj) = value.as_object_mut() {
+        if strip_stats {
+            strip_counters(obj, plugin);
+        }
         for key in plugin.preserved_fields.keys() {
             if let Some(val) = obj.remove(key) {
                 obj.insert(format!("!{}", key), val);
@@ -29,10 +42,10 @@ pub fn delete_plugin(plugin_id: &str, output_dir: &Path) -> Result<(), String> {
     if path.exists() {
         fs::remove_file(&path).map_err(|e| format!("Failed to delete {:?}: {}", path, e))?;
         // Clean up empty owner directory
-        if let Some(parent) = path.parent() {
-            if parent != output_dir {
-                let _ = fs::remove_dir(parent); // ignore error if not empty
-            }
+        if let Some(parent) = path.parent()
+            && parent != output_dir
+        {
+            let _ = fs::remove_dir(parent); // ignore error if not empty
         }
         Ok(())
     } else {
@@ -40,6 +53,22 @@ pub fn delete_plugin(plugin_id: &str, output_dir: &Path) -> Result<(), String> {
     }
 }
 
+fn strip_counters(obj: &mut serde_json::Map<String, serde_json::Value>, plugin: &Plugin) {
+    let preserved = |field: &str| plugin.preserved_fields.contains_key(field);
+    for field in ["stars", "downloads"] {
+        if !preserved(field) {
+            obj.remove(field);
+        }
+    }
+    if !preserved("versions")
+        && let Some(versions) = obj.get_mut("versions").and_then(|v| v.as_array_mut())
+    {
+        for version in versions.iter_mut().filter_map(|v| v.as_object_mut()) {
+            version.remove("downloads");
+        }
+    }
+}
+
 fn plugin_path(output_dir: &Path, plugin_id: &str) -> std::path::PathBuf {
     output_dir.join(format!("{}.json", plugin_id))
 }
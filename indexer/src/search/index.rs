@@ -0,0 +1,514 @@
+//! In-memory mirror of an Orama database, filled one document at a time and
+//! written in the `seqproto` layout that `restore('seqproto', ...)` reads
+//! (with `patches/@orama%2Fplugin-data-persistence@3.1.18.patch` applied).
+//!
+//! Only the parts of Orama the search schema needs are modelled: radix trees
+//! for `string`, flat maps for `enum`/`enum[]`, AVL trees for `number`, the
+//! BM25 statistics of string properties and the sorter state.
+
+use super::orama::{FieldType, OramaDocument, SCHEMA};
+use super::seqproto::Serializer;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+
+const LANGUAGE: &str = "english";
+
+const NODE_RADIX: u32 = 1;
+const NODE_FLAT: u32 = 2;
+const NODE_AVL: u32 = 3;
+
+#[derive(Debug, Default)]
+struct RadixNode {
+    key: String,
+    subword: String,
+    word: String,
+    end: bool,
+    docs: Vec<u32>,
+    children: BTreeMap<char, RadixNode>,
+}
+
+impl RadixNode {
+    fn new(parent_word: &str, subword: &str, end: bool) -> Self {
+        Self {
+            key: subword.chars().next().map(String::from).unwrap_or_default(),
+            subword: subword.to_string(),
+            word: format!("{}{}", parent_word, subword),
+            end,
+            ..Default::default()
+        }
+    }
+
+    fn add_doc(&mut self, doc_id: u32) {
+        if !self.docs.contains(&doc_id) {
+            self.docs.push(doc_id);
+        }
+    }
+
+    /// Insert `rest`, the part of the word below this node, splitting edges
+    /// the same way `RadixNode.insert` does.
+    fn insert(&mut self, rest: &str, doc_id: u32) {
+        let Some(first) = rest.chars().next() else {
+            self.end = true;
+            self.add_doc(doc_id);
+            return;
+        };
+
+        let Some(child) = self.children.get_mut(&first) else {
+            let mut node = RadixNode::new(&self.word, rest, true);
+            node.add_doc(doc_id);
+            self.children.insert(first, node);
+            return;
+        };
+
+        let common = child
+            .subword
+            .char_indices()
+            .zip(rest.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(child.subword.len().min(rest.len()), |((i, _), _)| i);
+
+        if common == child.subword.len() {
+            child.insert(&rest[common..], doc_id);
+            return;
+        }
+
+        let mut child = self.children.remove(&first).unwrap_or_default();
+        let mut between = RadixNode::new(&self.word, &child.subword[..common], false);
+        child.subword = child.subword[common..].to_string();
+        child.key = child
+            .subword
+            .chars()
+            .next()
+            .map(String::from)
+            .unwrap_or_default();
+        if let Some(key) = child.subword.chars().next() {
+            between.children.insert(key, child);
+        }
+        between.insert(&rest[common..], doc_id);
+        self.children.insert(first, between);
+    }
+
+    fn serialize(&self, ser: &mut Serializer) {
+        ser.string(&self.word);
+        ser.string(&self.subword);
+        ser.boolean(self.end);
+        ser.string(&self.key);
+        ser.numbers(&self.docs);
+        ser.length(self.children.len());
+        for (key, child) in &self.children {
+            ser.string(&key.to_string());
+            child.serialize(ser);
+        }
+    }
+}
+
+/// BM25 parameters Orama keeps per string property.
+#[derive(Debug, Default)]
+struct TextStats {
+    frequencies: BTreeMap<u32, Vec<(String, f64)>>,
+    occurrences: BTreeMap<String, u32>,
+    field_lengths: BTreeMap<u32, usize>,
+    avg_field_length: f64,
+}
+
+#[derive(Debug)]
+enum FieldIndex {
+    Radix(RadixNode, TextStats),
+    Flat(BTreeMap<String, Vec<u32>>),
+    Avl(BTreeMap<u64, Vec<u32>>),
+}
+
+impl FieldIndex {
+    fn new(field_type: FieldType) -> Self {
+        match field_type {
+            FieldType::String => FieldIndex::Radix(RadixNode::default(), TextStats::default()),
+            FieldType::Enum | FieldType::EnumArray => FieldIndex::Flat(BTreeMap::new()),
+            FieldType::Number => FieldIndex::Avl(BTreeMap::new()),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            FieldIndex::Radix(..) => "Radix",
+            FieldIndex::Flat(_) => "Flat",
+            FieldIndex::Avl(_) => "AVL",
+        }
+    }
+}
+
+/// Orama's default tokenizer with stemming and stop words disabled: lowercase,
+/// split on anything outside the English word characters, deduplicate.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    text.to_lowercase()
+        .split(|c: char| {
+            !(c.is_ascii_alphanumeric()
+                || matches!(
+                    c,
+                    '_' | '\'' | '-' | 'à' | 'è' | 'é' | 'ì' | 'ò' | 'ó' | 'ù'
+                ))
+        })
+        .filter(|t| !t.is_empty())
+        .map(|t| {
+            t.chars()
+                .map(|c| match c {
+                    'à' => 'a',
+                    'è' | 'é' => 'e',
+                    'ì' => 'i',
+                    'ò' | 'ó' => 'o',
+                    'ù' => 'u',
+                    _ => c,
+                })
+                .collect::<String>()
+        })
+        .filter(|t| seen.insert(t.clone()))
+        .collect()
+}
+
+/// Values of one sortable property, in insertion order.
+#[derive(Debug)]
+struct SortColumn {
+    name: &'static str,
+    field_type: FieldType,
+    ordered: Vec<(u32, Value)>,
+}
+
+pub struct OramaIndex {
+    ids: Vec<String>,
+    known_ids: HashSet<String>,
+    docs: Vec<Map<String, Value>>,
+    fields: Vec<FieldIndex>,
+    sorts: Vec<SortColumn>,
+}
+
+impl Default for OramaIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OramaIndex {
+    pub fn new() -> Self {
+        Self {
+            ids: Vec::new(),
+            known_ids: HashSet::new(),
+            docs: Vec::new(),
+            fields: SCHEMA.iter().map(|(_, t)| FieldIndex::new(*t)).collect(),
+            sorts: SCHEMA
+                .iter()
+                .filter(|(_, t)| matches!(t, FieldType::String | FieldType::Number))
+                .map(|(name, t)| SortColumn {
+                    name,
+                    field_type: *t,
+                    ordered: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    pub fn insert(&mut self, doc: &OramaDocument) -> Result<(), String> {
+        let Value::Object(doc) =
+            serde_json::to_value(doc).map_err(|e| format!("Serialize error: {}", e))?
+        else {
+            return Err("Document is not an object".to_string());
+        };
+        let id = doc
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        if !self.known_ids.insert(id.clone()) {
+            return Err(format!("Document {} already exists", id));
+        }
+
+        self.ids.push(id);
+        let internal_id = self.ids.len() as u32;
+        let docs_count = self.ids.len() as f64;
+
+        for ((name, _), field) in SCHEMA.iter().zip(&mut self.fields) {
+            let value = doc.get(*name).unwrap_or(&Value::Null);
+            match field {
+                FieldIndex::Radix(tree, stats) => {
+                    let tokens = tokenize(value.as_str().unwrap_or_default());
+                    stats.avg_field_length = (stats.avg_field_length * (docs_count - 1.0)
+                        + tokens.len() as f64)
+                        / docs_count;
+                    stats.field_lengths.insert(internal_id, tokens.len());
+                    let tf = 1.0 / tokens.len().max(1) as f64;
+                    let frequencies = stats.frequencies.entry(internal_id).or_default();
+                    for token in &tokens {
+                        frequencies.push((token.clone(), tf));
+                        *stats.occurrences.entry(token.clone()).or_default() += 1;
+                        tree.insert(token, internal_id);
+                    }
+                }
+                FieldIndex::Flat(map) => {
+                    let values = match value {
+                        Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+                        Value::String(s) => vec![s.as_str()],
+                        _ => vec![],
+                    };
+                    for v in values {
+                        let ids = map.entry(v.to_string()).or_default();
+                        if !ids.contains(&internal_id) {
+                            ids.push(internal_id);
+                        }
+                    }
+                }
+                FieldIndex::Avl(tree) => {
+                    let key = value
+                        .as_u64()
+                        .ok_or_else(|| format!("Field {} is not a number", name))?;
+                    tree.entry(key).or_default().push(internal_id);
+                }
+            }
+        }
+
+        for column in &mut self.sorts {
+            if let Some(value) = doc.get(column.name) {
+                column.ordered.push((internal_id, value.clone()));
+            }
+        }
+
+        self.docs.push(doc);
+        Ok(())
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut ser = Serializer::new();
+
+        // internalDocumentIDStore
+        ser.length(self.ids.len());
+        for id in &self.ids {
+            ser.string(id);
+        }
+
+        // documentsStore
+        ser.number(self.docs.len() as f64);
+        ser.length(self.docs.len());
+        for (i, doc) in self.docs.iter().enumerate() {
+            ser.string(&(i + 1).to_string());
+            ser.length(doc.len());
+            for (field, value) in doc {
+                ser.string(field);
+                ser.value(value);
+            }
+        }
+
+        self.serialize_index(&mut ser);
+        ser.string(LANGUAGE);
+
+        // pinning rules
+        ser.length(0);
+
+        self.serialize_sorting(&mut ser);
+        ser.into_bytes()
+    }
+
+    fn serialize_index(&self, ser: &mut Serializer) {
+        ser.length(SCHEMA.len());
+        for ((name, field_type), field) in SCHEMA.iter().zip(&self.fields) {
+            ser.string(name);
+            ser.string(field.type_name());
+            ser.boolean(*field_type == FieldType::EnumArray);
+            match field {
+                FieldIndex::Radix(tree, _) => {
+                    ser.u32(NODE_RADIX);
+                    tree.serialize(ser);
+                }
+                FieldIndex::Flat(map) => {
+                    ser.u32(NODE_FLAT);
+                    ser.length(map.len());
+                    for (key, ids) in map {
+                        ser.string(key);
+                        ser.numbers(ids);
+                    }
+                }
+                FieldIndex::Avl(tree) => {
+                    ser.u32(NODE_AVL);
+                    ser.number(tree.len() as f64);
+                    let entries: Vec<_> = tree.iter().collect();
+                    serialize_avl(ser, &entries);
+                }
+            }
+        }
+
+        // vectorIndexes
+        ser.length(0);
+
+        ser.length(SCHEMA.len());
+        for (name, _) in SCHEMA {
+            ser.string(name);
+        }
+        ser.length(SCHEMA.len());
+        for (name, field_type) in SCHEMA {
+            ser.string(name);
+            ser.string(field_type.as_str());
+        }
+
+        let text: Vec<(&str, &TextStats)> = SCHEMA
+            .iter()
+            .zip(&self.fields)
+            .filter_map(|((name, _), field)| match field {
+                FieldIndex::Radix(_, stats) => Some((*name, stats)),
+                _ => None,
+            })
+            .collect();
+
+        ser.length(text.len());
+        for (name, stats) in &text {
+            ser.string(name);
+            ser.length(stats.frequencies.len());
+            for (doc_id, tokens) in &stats.frequencies {
+                ser.string(&doc_id.to_string());
+                ser.length(tokens.len());
+                for (token, tf) in tokens {
+                    ser.string(token);
+                    ser.number(*tf);
+                }
+            }
+        }
+
+        ser.length(text.len());
+        for (name, stats) in &text {
+            ser.string(name);
+            ser.length(stats.occurrences.len());
+            for (token, count) in &stats.occurrences {
+                ser.string(token);
+                ser.number(*count as f64);
+            }
+        }
+
+        ser.length(text.len());
+        for (name, stats) in &text {
+            ser.string(name);
+            ser.number(stats.avg_field_length);
+        }
+
+        ser.length(text.len());
+        for (name, stats) in &text {
+            ser.string(name);
+            ser.length(stats.field_lengths.len());
+            for (doc_id, len) in &stats.field_lengths {
+                ser.string(&doc_id.to_string());
+                ser.number(*len as f64);
+            }
+        }
+    }
+
+    /// Sort data is written unsorted with `isSorted = false`; Orama orders
+    /// each property on the first `sortBy` query, using its own collation.
+    fn serialize_sorting(&self, ser: &mut Serializer) {
+        ser.boolean(true);
+        ser.boolean(false);
+        ser.string(LANGUAGE);
+
+        ser.length(self.sorts.len());
+        for column in &self.sorts {
+            ser.string(column.name);
+        }
+        ser.length(self.sorts.len());
+        for column in &self.sorts {
+            ser.string(column.name);
+            ser.string(column.field_type.as_str());
+        }
+
+        ser.length(self.sorts.len());
+        for column in &self.sorts {
+            ser.string(column.name);
+            ser.string(column.field_type.as_str());
+            ser.length(column.ordered.len());
+            for (position, (doc_id, _)) in column.ordered.iter().enumerate() {
+                ser.string(&doc_id.to_string());
+                ser.number(position as f64);
+            }
+            ser.length(column.ordered.len());
+            for (doc_id, value) in &column.ordered {
+                ser.number(*doc_id as f64);
+                ser.value(value);
+            }
+        }
+    }
+}
+
+/// Write `entries` (sorted by key) as a height-balanced AVL tree.
+fn serialize_avl(ser: &mut Serializer, entries: &[(&u64, &Vec<u32>)]) {
+    if entries.is_empty() {
+        ser.boolean(false);
+        return;
+    }
+    let mid = entries.len() / 2;
+    let (key, ids) = entries[mid];
+    let height = avl_height(entries.len());
+
+    ser.boolean(true);
+    ser.number(*key as f64);
+    ser.numbers(ids);
+    ser.number(height as f64);
+    serialize_avl(ser, &entries[..mid]);
+    serialize_avl(ser, &entries[mid + 1..]);
+}
+
+fn avl_height(len: usize) -> u32 {
+    usize::BITS - len.leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(node: &RadixNode, out: &mut Vec<(String, Vec<u32>)>) {
+        if node.end {
+            out.push((node.word.clone(), node.docs.clone()));
+        }
+        for child in node.children.values() {
+            words(child, out);
+        }
+    }
+
+    #[test]
+    fn test_radix_split_keeps_words() {
+        let mut root = RadixNode::default();
+        root.insert("allay", 1);
+        root.insert("all", 2);
+        root.insert("alloy", 3);
+        root.insert("allay", 4);
+
+        let mut out = Vec::new();
+        words(&root, &mut out);
+        assert_eq!(
+            out,
+            vec![
+                ("all".to_string(), vec![2]),
+                ("allay".to_string(), vec![1, 4]),
+                ("alloy".to_string(), vec![3]),
+            ]
+        );
+        let all = &root.children[&'a'];
+        assert_eq!(all.subword, "all");
+        assert_eq!(all.children[&'a'].subword, "ay");
+        assert_eq!(all.children[&'a'].key, "a");
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("Hello World hello"), vec!["hello", "world"]);
+        assert_eq!(tokenize("  "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_avl_height() {
+        assert_eq!(avl_height(1), 1);
+        assert_eq!(avl_height(3), 2);
+        assert_eq!(avl_height(4), 3);
+        assert_eq!(avl_height(7), 3);
+    }
+}
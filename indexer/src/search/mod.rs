@@ -1,5 +1,8 @@
+pub mod index;
 pub mod orama;
 pub mod segment;
+pub mod seqproto;
 
-pub use orama::{OramaDocument, build_orama_index};
+pub use index::OramaIndex;
+pub use orama::{FieldType, OramaDocument, SCHEMA, build_documents, build_orama_index};
 pub use segment::{get_segmenter, split_identifier};
@@ -2,20 +2,55 @@ use crate::plugin::Plugin;
 use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Write;
 use std::path::Path;
-use std::process::{Command, Stdio};
 use tracing::{debug, error, info_span};
 
+use super::index::OramaIndex;
 use super::segment::{get_segmenter, split_identifier};
 use instant_segment::Search;
 
+/// Orama property types used by the search schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    String,
+    Number,
+    Enum,
+    EnumArray,
+}
+
+impl FieldType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FieldType::String => "string",
+            FieldType::Number => "number",
+            FieldType::Enum => "enum",
+            FieldType::EnumArray => "enum[]",
+        }
+    }
+}
+
+/// Indexed properties of `OramaDocument`. Must match `PluginSearchDB` in
+/// `src/composables/orama-loader.ts`; every other field is stored but not
+/// searchable.
+pub const SCHEMA: &[(&str, FieldType)] = &[
+    ("name", FieldType::String),
+    ("owner", FieldType::String),
+    ("categories", FieldType::EnumArray),
+    ("license", FieldType::Enum),
+    ("api_major", FieldType::Number),
+    ("downloads", FieldType::Number),
+    ("stars", FieldType::Number),
+    ("created_at", FieldType::Number),
+    ("updated_at", FieldType::Number),
+];
+
 #[derive(Debug, Serialize)]
 pub struct OramaDocument {
     pub name: String,
     pub owner: String,
     pub categories: Vec<String>,
     pub api_version: String,
+    pub api_major: u64,
     pub license: String,
     pub downloads: u64,
     pub stars: u64,
@@ -37,6 +72,26 @@ fn get_license_type(license: &crate::plugin::License) -> &'static str {
     }
 }
 
+/// Major version of a strict semver string, `0` for anything else
+/// (ranges such as `>=0.23.0` included), like `semver.parse(v)?.major`.
+pub fn api_major(version: &str) -> u64 {
+    let version = version.trim();
+    let version = version
+        .strip_prefix('=')
+        .or_else(|| version.strip_prefix('v'))
+        .unwrap_or(version);
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+    if parts.len() != 3
+        || parts
+            .iter()
+            .any(|p| p.is_empty() || !p.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return 0;
+    }
+    parts[0].parse().unwrap_or(0)
+}
+
 fn build_document(plugin: &Plugin, split_cache: &HashMap<String, Vec<String>>) -> OramaDocument {
     let author = plugin.get_author_name();
 
@@ -59,6 +114,7 @@ fn build_document(plugin: &Plugin, split_cache: &HashMap<String, Vec<String>>) -
         owner: owner_tokens.join(" "),
         categories: plugin.categories.clone(),
         api_version: plugin.api_version.clone(),
+        api_major: api_major(&plugin.api_version),
         license: get_license_type(&plugin.license).to_string(),
         downloads: plugin.downloads,
         stars: plugin.stars,
@@ -77,15 +133,9 @@ fn build_document(plugin: &Plugin, split_cache: &HashMap<String, Vec<String>>) -
     }
 }
 
-pub fn build_orama_index(plugins: &[Plugin], output_path: &Path, builder_path: &Path) -> bool {
-    let _span = info_span!("build_orama_index", plugins = plugins.len()).entered();
-
-    if let Some(parent) = output_path.parent()
-        && let Err(e) = fs::create_dir_all(parent) {
-            error!(error = %e, "Failed to create output directory");
-            return false;
-        }
-
+/// The search documents for `plugins`, with names and authors split into
+/// words.
+pub fn build_documents(plugins: &[Plugin]) -> Vec<OramaDocument> {
     let segmenter = get_segmenter();
     let mut identifiers: HashSet<String> = HashSet::new();
 
@@ -106,51 +156,52 @@ pub fn build_orama_index(plugins: &[Plugin], output_path: &Path, builder_path: &
         })
         .collect();
 
-    let docs: Vec<OramaDocument> = plugins
+    plugins
         .iter()
         .map(|p| build_document(p, &split_cache))
-        .collect();
+        .collect()
+}
 
-    let json = serde_json::to_string(&docs).unwrap_or_default();
-
-    let mut child = match Command::new("bun")
-        .args([
-            "run",
-            &builder_path.to_string_lossy(),
-            &output_path.to_string_lossy(),
-        ])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-    {
-        Ok(c) => c,
-        Err(e) => {
-            error!(error = %e, "Failed to spawn bun");
+pub fn build_orama_index(plugins: &[Plugin], output_path: &Path) -> bool {
+    let _span = info_span!("build_orama_index", plugins = plugins.len()).entered();
+
+    if let Some(parent) = output_path.parent()
+        && let Err(e) = fs::create_dir_all(parent) {
+            error!(error = %e, "Failed to create output directory");
             return false;
         }
-    };
 
-    if let Some(mut stdin) = child.stdin.take()
-        && let Err(e) = stdin.write_all(json.as_bytes()) {
-            error!(error = %e, "Failed to write to stdin");
+    let mut index = OramaIndex::new();
+    for (plugin, doc) in plugins.iter().zip(build_documents(plugins)) {
+        if let Err(e) = index.insert(&doc) {
+            error!(id = %plugin.id, error = %e, "Failed to index plugin");
             return false;
         }
+    }
 
-    match child.wait_with_output() {
-        Ok(output) => {
-            if !output.status.success() {
-                error!(stderr = %String::from_utf8_lossy(&output.stderr), "Orama builder failed");
-                return false;
-            }
-            if !output.stdout.is_empty() {
-                debug!(stdout = %String::from_utf8_lossy(&output.stdout), "Orama builder output");
-            }
+    match fs::write(output_path, index.serialize()) {
+        Ok(()) => {
+            debug!(documents = index.len(), "Orama index written");
             true
         }
         Err(e) => {
-            error!(error = %e, "Failed to wait for bun");
+            error!(error = %e, "Failed to write index");
             false
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_major() {
+        assert_eq!(api_major("1.2.3"), 1);
+        assert_eq!(api_major("v2.0.0-beta.1"), 2);
+        assert_eq!(api_major("0.23.0"), 0);
+        assert_eq!(api_major(">=1.0.0"), 0);
+        assert_eq!(api_major("1.2"), 0);
+        assert_eq!(api_major(""), 0);
+    }
+}
@@ -0,0 +1,114 @@
+//! Encoder for the `seqproto` wire format used by
+//! `@orama/plugin-data-persistence`.
+//!
+//! Everything is written as little-endian 32-bit words, the layout of the
+//! `Uint32Array` the JS deserializer reads from:
+//!
+//! - booleans and `u32`s take one word
+//! - numbers take a type tag word (`0` float32, `1` uint32, `2` int32)
+//!   followed by the value
+//! - strings take a byte-length word followed by UTF-8 bytes padded to a
+//!   word boundary
+//!
+//! [`Serializer::value`] mirrors `serializeValue` from the patched
+//! `seqproto.js` and is used for documents and sort values.
+
+use serde_json::Value;
+
+const NUMBER_FLOAT: u32 = 0;
+const NUMBER_UINT32: u32 = 1;
+const NUMBER_INT32: u32 = 2;
+
+const VALUE_NULL: u32 = 0;
+const VALUE_STRING: u32 = 1;
+const VALUE_NUMBER: u32 = 2;
+const VALUE_BOOLEAN: u32 = 3;
+const VALUE_ARRAY: u32 = 4;
+const VALUE_OBJECT: u32 = 5;
+
+#[derive(Debug, Default)]
+pub struct Serializer {
+    buf: Vec<u8>,
+}
+
+impl Serializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn boolean(&mut self, b: bool) {
+        self.u32(b as u32);
+    }
+
+    pub fn u32(&mut self, n: u32) {
+        self.buf.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn length(&mut self, n: usize) {
+        self.u32(n as u32);
+    }
+
+    pub fn number(&mut self, n: f64) {
+        if n.fract() != 0.0 || !n.is_finite() {
+            self.u32(NUMBER_FLOAT);
+            self.buf.extend_from_slice(&(n as f32).to_le_bytes());
+        } else if n >= 0.0 {
+            self.u32(NUMBER_UINT32);
+            self.u32(n as u32);
+        } else {
+            self.u32(NUMBER_INT32);
+            self.u32((n as i64 + (1i64 << 32)) as u32);
+        }
+    }
+
+    pub fn string(&mut self, s: &str) {
+        self.length(s.len());
+        self.buf.extend_from_slice(s.as_bytes());
+        let padding = (4 - s.len() % 4) % 4;
+        self.buf.extend(std::iter::repeat_n(0, padding));
+    }
+
+    pub fn numbers(&mut self, values: &[u32]) {
+        self.length(values.len());
+        for &v in values {
+            self.number(v as f64);
+        }
+    }
+
+    pub fn value(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.u32(VALUE_NULL),
+            Value::String(s) => {
+                self.u32(VALUE_STRING);
+                self.string(s);
+            }
+            Value::Number(n) => {
+                self.u32(VALUE_NUMBER);
+                self.number(n.as_f64().unwrap_or(0.0));
+            }
+            Value::Bool(b) => {
+                self.u32(VALUE_BOOLEAN);
+                self.boolean(*b);
+            }
+            Value::Array(items) => {
+                self.u32(VALUE_ARRAY);
+                self.length(items.len());
+                for item in items {
+                    self.value(item);
+                }
+            }
+            Value::Object(map) => {
+                self.u32(VALUE_OBJECT);
+                self.length(map.len());
+                for (key, item) in map {
+                    self.string(key);
+                    self.value(item);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::github::{GitHubError, GitTreeEntry, client};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Where the builder reads repository files from: the GitHub API for indexed
+/// repositories, or a checkout on disk for `inspect --path`.
+pub(crate) trait RepoFiles {
+    /// Every file in the repository, with `/`-separated paths.
+    fn tree(&self) -> &[GitTreeEntry];
+
+    fn read(&self, path: &str) -> Result<String, GitHubError>;
+}
+
+pub(crate) struct GitHubFiles<'a> {
+    owner: &'a str,
+    repo: &'a str,
+    tree: Vec<GitTreeEntry>,
+}
+
+impl<'a> GitHubFiles<'a> {
+    pub fn fetch(owner: &'a str, repo: &'a str, branch: &str) -> Result<Self, GitHubError> {
+        let tree = client().get_tree(owner, repo, branch)?.tree;
+        Ok(Self { owner, repo, tree })
+    }
+}
+
+impl RepoFiles for GitHubFiles<'_> {
+    fn tree(&self) -> &[GitTreeEntry] {
+        &self.tree
+    }
+
+    fn read(&self, path: &str) -> Result<String, GitHubError> {
+        match self.tree.iter().find(|e| e.path == path) {
+            Some(entry) => client().get_blob(self.owner, self.repo, path, &entry.sha),
+            None => client().get_file_content(self.owner, self.repo, path),
+        }
+    }
+}
+
+/// Directories that never hold sources the indexer looks at.
+const SKIPPED_DIRS: &[&str] = &[
+    ".git",
+    ".gradle",
+    ".idea",
+    "build",
+    "out",
+    "target",
+    "node_modules",
+];
+
+pub(crate) struct LocalFiles {
+    root: PathBuf,
+    tree: Vec<GitTreeEntry>,
+}
+
+impl LocalFiles {
+    pub fn new(root: &Path) -> Result<Self, String> {
+        if !root.is_dir() {
+            return Err(format!("{:?} is not a directory", root));
+        }
+        let mut tree = Vec::new();
+        walk(root, "", &mut tree);
+        tree.sort_by(|a, b| a.path.cmp(&b.path));
+        debug!(root = ?root, files = tree.len(), "Walked local checkout");
+        Ok(Self {
+            root: root.to_path_buf(),
+            tree,
+        })
+    }
+}
+
+fn walk(dir: &Path, prefix: &str, tree: &mut Vec<GitTreeEntry>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_str()) {
+                walk(&entry.path(), &path, tree);
+            }
+        } else if file_type.is_file() {
+            tree.push(GitTreeEntry {
+                path,
+                entry_type: "blob".to_string(),
+                sha: String::new(),
+                size: entry.metadata().ok().map(|m| m.len()),
+            });
+        }
+    }
+}
+
+impl RepoFiles for LocalFiles {
+    fn tree(&self) -> &[GitTreeEntry] {
+        &self.tree
+    }
+
+    fn read(&self, path: &str) -> Result<String, GitHubError> {
+        if !self.tree.iter().any(|e| e.path == path) {
+            return Err(GitHubError::NotFound);
+        }
+        fs::read_to_string(self.root.join(path))
+            .map_err(|e| GitHubError::Parse(format!("Failed to read {}: {}", path, e)))
+    }
+}
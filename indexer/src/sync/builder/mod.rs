@@ -1,10 +1,11 @@
+mod files;
 mod image;
 mod link;
 mod version_resolver;
 
-use crate::github::{Contributor, GitTreeEntry, Release, Repository, client};
+use crate::github::{Contributor, GitHubError, GitTreeEntry, Release, Repository, client};
 
-const CATEGORIES: &[&str] = &[
+pub(crate) const CATEGORIES: &[&str] = &[
     "adventure",
     "cursed",
     "decoration",
@@ -25,10 +26,17 @@ const CATEGORIES: &[&str] = &[
     "utility",
     "world-generation",
 ];
-use crate::gradle::{AllayDsl, VersionRef, parse_build_gradle, parse_build_gradle_kts, parse_gradle_settings, parse_plugin_json};
+use crate::gradle::{
+    AllayDsl, ConventionPlugins, GradleProperties, VersionCatalogs, VersionRef,
+    is_script_plugin_path, parse_gradle_script, parse_gradle_settings, parse_plugin_json,
+    script_plugin_id,
+};
 use crate::plugin::{
     Author, Dependency, GalleryItem, License, Links, Plugin, Version, VersionFile,
 };
+use files::{GitHubFiles, LocalFiles, RepoFiles};
+use std::fs;
+use std::path::Path;
 use tracing::debug;
 
 pub struct PostProcessContext<'a> {
@@ -40,6 +48,8 @@ pub struct PostProcessContext<'a> {
 struct PluginBuildInput<'a> {
     repo: &'a Repository,
     dsl: &'a AllayDsl,
+    fallback_name: &'a str,
+    asset_filter: Option<(&'a AssetMatcher, usize)>,
     releases: &'a [Release],
     readme: &'a str,
     license: &'a License,
@@ -97,13 +107,6 @@ fn plugin_json_paths_for_module(module: &str) -> Vec<String> {
     ]
 }
 
-fn get_tree(owner: &str, repo: &str, branch: &str) -> Vec<GitTreeEntry> {
-    client()
-        .get_tree(owner, repo, branch)
-        .map(|t| t.tree)
-        .unwrap_or_default()
-}
-
 fn find_gradle_paths_from_tree(tree: &[GitTreeEntry]) -> Vec<String> {
     tree.iter()
         .filter(|e| {
@@ -115,12 +118,80 @@ fn find_gradle_paths_from_tree(tree: &[GitTreeEntry]) -> Vec<String> {
         .collect()
 }
 
-fn parse_gradle_file(path: &str, content: &str) -> Option<AllayDsl> {
-    if path.ends_with(".gradle.kts") {
-        parse_build_gradle_kts(content)
+/// Precompiled script plugins of `buildSrc` and `build-logic`, resolved
+/// against the root `gradle.properties` that the applying projects see.
+fn load_convention_plugins(
+    files: &dyn RepoFiles,
+    catalogs: &VersionCatalogs,
+) -> Result<ConventionPlugins, GitHubError> {
+    let mut conventions = ConventionPlugins::default();
+    let mut root_properties: Option<GradleProperties> = None;
+
+    let paths = files
+        .tree()
+        .iter()
+        .filter(|e| e.entry_type == "blob" && is_script_plugin_path(&e.path))
+        .map(|e| e.path.clone())
+        .collect::<Vec<_>>();
+    for path in paths {
+        let content = match files.read(&path) {
+            Ok(c) => c,
+            Err(GitHubError::NotFound) => continue,
+            Err(e) => return Err(e),
+        };
+        let Some(id) = script_plugin_id(&path, &content) else {
+            continue;
+        };
+        let root = match &root_properties {
+            Some(root) => root,
+            None => root_properties.insert(read_gradle_properties(files, "")?),
+        };
+        if let Some(dsl) = parse_gradle_script(&path, &content, root, catalogs) {
+            debug!(path = %path, id = %id, "Found convention plugin");
+            conventions.insert(&id, &content, dsl);
+        }
+    }
+
+    Ok(conventions)
+}
+
+/// Read `gradle.properties` in `dir` (`""` for the root), or nothing if there
+/// is none. Other read failures are returned, like those of Gradle files, so
+/// a flaky fetch can't resolve versions against missing properties.
+fn read_gradle_properties(
+    files: &dyn RepoFiles,
+    dir: &str,
+) -> Result<GradleProperties, GitHubError> {
+    let path = if dir.is_empty() {
+        "gradle.properties".to_string()
     } else {
-        parse_build_gradle(content)
+        format!("{}/gradle.properties", dir)
+    };
+    if !tree_has_file(files.tree(), &path) {
+        return Ok(GradleProperties::default());
     }
+    match files.read(&path) {
+        Ok(content) => Ok(GradleProperties::parse(&content)),
+        Err(GitHubError::NotFound) => {
+            debug!(path = %path, "Skip: gradle.properties not found");
+            Ok(GradleProperties::default())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Properties visible to the build script at `gradle_path`: the root
+/// `gradle.properties`, overridden by the module's own.
+fn module_properties(
+    files: &dyn RepoFiles,
+    gradle_path: &str,
+    root: &GradleProperties,
+) -> Result<GradleProperties, GitHubError> {
+    let mut props = root.clone();
+    if let Some((dir, _)) = gradle_path.rsplit_once('/') {
+        props.extend(&read_gradle_properties(files, dir)?);
+    }
+    Ok(props)
 }
 
 fn is_branch_snapshot(version: &str) -> bool {
@@ -128,29 +199,51 @@ fn is_branch_snapshot(version: &str) -> bool {
     lower.ends_with("-snapshot") && !lower.chars().next().is_some_and(|c| c.is_ascii_digit())
 }
 
-fn resolve_dsl_versions(dsl: &mut AllayDsl, tree: &[GitTreeEntry], owner: &str, repo: &str) {
-    if !matches!(dsl.api_version_ref, VersionRef::Literal(_))
-        && let Some(v) = version_resolver::resolve_version(&dsl.api_version_ref, tree, owner, repo)
-        {
+fn resolve_dsl_versions(
+    dsl: &mut AllayDsl,
+    files: &dyn RepoFiles,
+    props: &GradleProperties,
+    catalogs: &VersionCatalogs,
+) {
+    if !matches!(dsl.api_version_ref, VersionRef::Literal(_) | VersionRef::None) {
+        let resolved = version_resolver::resolve_version(
+            &dsl.api_version_ref,
+            "api",
+            files,
+            props,
+            catalogs,
+        );
+        debug!(version_ref = ?dsl.api_version_ref, resolved = ?resolved, "Resolved API version reference");
+        if let Some(v) = resolved {
             dsl.api = Some(v);
         }
+    }
 
-    if !matches!(dsl.server_version_ref, VersionRef::Literal(_))
-        && let Some(v) =
-            version_resolver::resolve_version(&dsl.server_version_ref, tree, owner, repo)
-        {
+    if !matches!(dsl.server_version_ref, VersionRef::Literal(_) | VersionRef::None) {
+        let resolved = version_resolver::resolve_version(
+            &dsl.server_version_ref,
+            "server",
+            files,
+            props,
+            catalogs,
+        );
+        debug!(version_ref = ?dsl.server_version_ref, resolved = ?resolved, "Resolved server version reference");
+        if let Some(v) = resolved {
             dsl.server = Some(v);
         }
+    }
 
     if let Some(api) = &dsl.api
         && is_branch_snapshot(api)
             && let Some(v) = version_resolver::resolve_snapshot_version() {
+                debug!(snapshot = %api, resolved = %v, "Resolved branch snapshot API version");
                 dsl.api = Some(v);
             }
 
     if let Some(server) = &dsl.server
         && is_branch_snapshot(server)
             && let Some(v) = version_resolver::resolve_snapshot_version() {
+                debug!(snapshot = %server, resolved = %v, "Resolved branch snapshot server version");
                 dsl.server = Some(v);
             }
 }
@@ -158,25 +251,26 @@ fn resolve_dsl_versions(dsl: &mut AllayDsl, tree: &[GitTreeEntry], owner: &str,
 struct SettingsMetadata {
     project_name: Option<String>,
     project_version: Option<String>,
+    /// AllayGradle version from `pluginManagement`, possibly a template.
+    allay_gradle_version: Option<String>,
 }
 
-fn find_settings_metadata(
-    owner: &str,
-    repo_name: &str,
-    tree: &[GitTreeEntry],
-) -> SettingsMetadata {
+fn find_settings_metadata(files: &dyn RepoFiles) -> SettingsMetadata {
     let settings_paths = ["settings.gradle.kts", "settings.gradle"];
     for path in settings_paths {
-        if tree
-            .iter()
-            .any(|e| e.path == path && e.entry_type == "blob")
-            && let Ok(content) = client().get_file_content(owner, repo_name, path)
+        if tree_has_file(files.tree(), path)
+            && let Ok(content) = files.read(path)
         {
             let dsl = parse_gradle_settings(path, &content);
-            if dsl.project_name.is_some() || dsl.project_version.is_some() {
+            let allay_gradle_version = dsl.allay_gradle.and_then(|g| g.version);
+            if dsl.project_name.is_some()
+                || dsl.project_version.is_some()
+                || allay_gradle_version.is_some()
+            {
                 return SettingsMetadata {
                     project_name: dsl.project_name,
                     project_version: dsl.project_version,
+                    allay_gradle_version,
                 };
             }
         }
@@ -184,6 +278,7 @@ fn find_settings_metadata(
     SettingsMetadata {
         project_name: None,
         project_version: None,
+        allay_gradle_version: None,
     }
 }
 
@@ -253,110 +348,249 @@ fn find_gallery_items(tree: &[GitTreeEntry], owner: &str, repo: &str, branch: &s
     gallery
 }
 
-fn find_first_allay_dsl(
-    owner: &str,
-    repo_name: &str,
+/// A Gradle module that declares an Allay plugin, either through the DSL or a
+/// `plugin.json` in its resources.
+struct AllayModule {
+    /// Module directory relative to the repository root, or `"root"`.
+    module: String,
+    dsl: AllayDsl,
+    /// The `gradle.properties` visible to the module's build script.
+    properties: GradleProperties,
+}
+
+fn find_allay_modules(
+    files: &dyn RepoFiles,
+    catalogs: &VersionCatalogs,
+    conventions: &ConventionPlugins,
     paths: &[String],
     full_name: &str,
-    tree: &[GitTreeEntry],
-) -> Option<AllayDsl> {
+) -> Result<Vec<AllayModule>, GitHubError> {
     let mut settings_meta: Option<SettingsMetadata> = None;
+    let mut root_properties: Option<GradleProperties> = None;
+    let mut modules = Vec::new();
 
     for gradle_path in paths {
-        let content = match client().get_file_content(owner, repo_name, gradle_path) {
+        let content = match files.read(gradle_path) {
             Ok(c) => c,
-            Err(e) => {
-                debug!(repo = %full_name, path = %gradle_path, error = %e, "Skip: failed to get gradle file");
+            Err(GitHubError::NotFound) => {
+                debug!(repo = %full_name, path = %gradle_path, "Skip: gradle file not found");
                 continue;
             }
+            // Skipping on anything else could drop a module that still exists.
+            Err(e) => return Err(e),
         };
 
-        if !content.contains("org.allaymc") {
+        if !content.contains("org.allaymc")
+            && !catalogs.references_allay(&content)
+            && !conventions.applied_by(&content)
+        {
             debug!(repo = %full_name, path = %gradle_path, "Skip: no org.allaymc dependency");
             continue;
         }
 
-        let mut dsl = match parse_gradle_file(gradle_path, &content) {
+        let root = match &root_properties {
+            Some(root) => root,
+            None => root_properties.insert(read_gradle_properties(files, "")?),
+        };
+        let properties = module_properties(files, gradle_path, root)?;
+
+        let mut dsl = match parse_gradle_script(gradle_path, &content, &properties, catalogs) {
             Some(d) => d,
             None => {
                 debug!(repo = %full_name, path = %gradle_path, "Skip: failed to parse gradle file");
                 continue;
             }
         };
+        conventions.apply(&content, &mut dsl);
+        if !dsl.is_allay_project() {
+            debug!(repo = %full_name, path = %gradle_path, "Skip: no Allay declarations");
+            continue;
+        }
 
-        let meta = settings_meta.get_or_insert_with(|| {
-            find_settings_metadata(owner, repo_name, tree)
-        });
+        let meta = settings_meta.get_or_insert_with(|| find_settings_metadata(files));
         if dsl.project_name.is_none() {
             dsl.project_name = meta.project_name.clone();
         }
         if dsl.project_version.is_none() {
             dsl.project_version = meta.project_version.clone();
         }
+        if let Some(allay_gradle) = &mut dsl.allay_gradle
+            && allay_gradle.version.is_none()
+        {
+            allay_gradle.version = meta
+                .allay_gradle_version
+                .as_deref()
+                .and_then(|v| properties.interpolate(v));
+        }
 
-        if dsl.plugin.is_none() && dsl.has_allay_dependency
-            && let Some(module) = gradle_path_to_module(gradle_path) {
-                for json_path in plugin_json_paths_for_module(&module) {
-                    if !tree_has_file(tree, &json_path) {
-                        continue;
-                    }
-                    if let Ok(json_content) =
-                        client().get_file_content(owner, repo_name, &json_path)
-                        && let Some(json) = parse_plugin_json(&json_content)
-                            && json.entrance.is_some() {
-                                dsl.plugin = Some(json.into_plugin_dsl(
-                                    dsl.project_name.as_deref(),
-                                    dsl.project_version.as_deref(),
-                                    dsl.project_description.as_deref(),
-                                ));
-                                break;
-                            }
+        let module = gradle_path_to_module(gradle_path).unwrap_or_else(|| "root".to_string());
+
+        let mut source = "allay DSL";
+        if dsl.plugin.is_none() && (dsl.has_allay_dependency || dsl.allay_gradle.is_some()) {
+            for json_path in plugin_json_paths_for_module(&module) {
+                if !tree_has_file(files.tree(), &json_path) {
+                    continue;
+                }
+                if let Ok(json_content) = files.read(&json_path)
+                    && let Some(json) = parse_plugin_json(&json_content)
+                    && json.entrance.is_some()
+                {
+                    dsl.plugin = Some(json.into_plugin_dsl(
+                        dsl.project_name.as_deref(),
+                        dsl.project_version.as_deref(),
+                        dsl.project_description.as_deref(),
+                    ));
+                    source = "plugin.json";
+                    break;
                 }
             }
+        }
 
-        if dsl.plugin.is_some() || dsl.has_allay_dependency {
-            return Some(dsl);
+        if dsl.plugin.is_some() {
+            debug!(repo = %full_name, path = %gradle_path, module = %module, source, "Found plugin module");
+            modules.push(AllayModule {
+                module,
+                dsl,
+                properties,
+            });
+        } else {
+            debug!(repo = %full_name, path = %gradle_path, "Skip: no plugin DSL or plugin.json");
         }
     }
 
-    None
+    Ok(modules)
 }
 
-pub fn build_plugins_from_repo(repo: &Repository, gradle_paths: &[String]) -> Vec<Plugin> {
+/// Build the plugins declared in `repo`. Failures to fetch the tree, Gradle
+/// files, releases or README are returned rather than yielding a partial
+/// plugin; contributors are best effort.
+pub fn build_plugins_from_repo(
+    repo: &Repository,
+    gradle_paths: &[String],
+) -> Result<Vec<Plugin>, GitHubError> {
     let (owner, repo_name) = match repo.full_name.split_once('/') {
         Some((o, r)) => (o, r),
         None => {
             debug!(repo = %repo.full_name, "Skip: invalid repo name");
-            return Vec::new();
+            return Ok(Vec::new());
         }
     };
 
     let default_branch = repo.default_branch.as_deref().unwrap_or("main");
 
-    let tree = get_tree(owner, repo_name, default_branch);
+    let files = GitHubFiles::fetch(owner, repo_name, default_branch)?;
+    let modules = find_repo_modules(repo, &files, gradle_paths)?;
+    if modules.is_empty() {
+        return Ok(Vec::new());
+    }
 
-    let paths_to_check = if gradle_paths.is_empty() {
-        find_gradle_paths_from_tree(&tree)
+    let releases = client().get_releases(owner, repo_name)?;
+    let readme = match find_root_readme(files.tree()) {
+        Some(entry) => client().get_readme_blob(owner, repo_name, &entry.sha),
+        None => client().get_readme(owner, repo_name),
+    }?;
+    let contributors = client()
+        .get_contributors_by_url(&repo.contributors_url)
+        .unwrap_or_else(|e| {
+            debug!(repo = %repo.full_name, error = %e, "Failed to get contributors");
+            Vec::new()
+        });
+
+    Ok(assemble_plugins(
+        repo,
+        files.tree(),
+        &modules,
+        &releases,
+        &readme,
+        &contributors,
+    ))
+}
+
+/// Build the plugins of a local checkout the way `build_plugins_from_repo`
+/// would after a push.
+///
+/// The repository identity comes from the `origin` remote in `.git/config`
+/// and the branch from `.git/HEAD`. Data that only GitHub has (releases,
+/// stars, contributors, the detected license, topics) is left empty.
+pub fn build_plugins_from_local(root: &Path) -> Result<Vec<Plugin>, String> {
+    let files = LocalFiles::new(root)?;
+    let repo = local_repository(root);
+
+    let modules = find_repo_modules(&repo, &files, &[]).map_err(|e| e.to_string())?;
+    if modules.is_empty() {
+        return Err("No Allay plugin module found".to_string());
+    }
+
+    let readme = find_root_readme(files.tree())
+        .and_then(|e| files.read(&e.path).ok())
+        .unwrap_or_default();
+
+    Ok(assemble_plugins(
+        &repo,
+        files.tree(),
+        &modules,
+        &[],
+        &readme,
+        &[],
+    ))
+}
+
+fn find_root_readme(tree: &[GitTreeEntry]) -> Option<&GitTreeEntry> {
+    tree.iter().find(|e| {
+        e.entry_type == "blob"
+            && !e.path.contains('/')
+            && e.path.to_lowercase().starts_with("readme")
+    })
+}
+
+/// Find the Allay plugin modules of a repository and resolve their versions.
+fn find_repo_modules(
+    repo: &Repository,
+    files: &dyn RepoFiles,
+    gradle_paths: &[String],
+) -> Result<Vec<AllayModule>, GitHubError> {
+    let mut paths_to_check = if gradle_paths.is_empty() {
+        find_gradle_paths_from_tree(files.tree())
     } else {
         gradle_paths.to_vec()
     };
 
-    let mut dsl =
-        match find_first_allay_dsl(owner, repo_name, &paths_to_check, &repo.full_name, &tree) {
-            Some(d) => d,
-            None => {
-                debug!(repo = %repo.full_name, "Skip: no valid gradle modules found");
-                return Vec::new();
+    let catalogs = version_resolver::load_version_catalogs(files);
+    let conventions = load_convention_plugins(files, &catalogs)?;
+    if !conventions.is_empty() {
+        // Code search only finds the scripts that mention Allay themselves,
+        // not the modules that just apply a convention plugin.
+        for path in find_gradle_paths_from_tree(files.tree()) {
+            if !paths_to_check.contains(&path) {
+                paths_to_check.push(path);
             }
-        };
+        }
+    }
+    let mut modules =
+        find_allay_modules(files, &catalogs, &conventions, &paths_to_check, &repo.full_name)?;
+    if modules.is_empty() {
+        debug!(repo = %repo.full_name, "Skip: no valid gradle modules found");
+        return Ok(modules);
+    }
 
-    resolve_dsl_versions(&mut dsl, &tree, owner, repo_name);
+    for module in &mut modules {
+        resolve_dsl_versions(&mut module.dsl, files, &module.properties, &catalogs);
+    }
+    Ok(modules)
+}
 
-    let releases = client().get_releases(owner, repo_name).unwrap_or_default();
-    let readme = client().get_readme(owner, repo_name).unwrap_or_default();
-    let contributors = client()
-        .get_contributors_by_url(&repo.contributors_url)
-        .unwrap_or_default();
+fn assemble_plugins(
+    repo: &Repository,
+    tree: &[GitTreeEntry],
+    modules: &[AllayModule],
+    releases: &[Release],
+    readme: &str,
+    contributors: &[Contributor],
+) -> Vec<Plugin> {
+    let Some((owner, repo_name)) = repo.full_name.split_once('/') else {
+        return Vec::new();
+    };
+    let default_branch = repo.default_branch.as_deref().unwrap_or("main");
 
     let license = repo.license.as_ref().map_or_else(
         || License {
@@ -387,34 +621,187 @@ pub fn build_plugins_from_repo(repo: &Repository, gradle_paths: &[String]) -> Ve
         },
     );
 
-    let icon_url = find_logo_url(&tree, owner, repo_name, default_branch)
+    let icon_url = find_logo_url(tree, owner, repo_name, default_branch)
         .unwrap_or_else(|| repo.owner.avatar_url.clone());
-    let repo_gallery = find_gallery_items(&tree, owner, repo_name, default_branch);
+    let repo_gallery = find_gallery_items(tree, owner, repo_name, default_branch);
 
-    let input = PluginBuildInput {
-        repo,
-        dsl: &dsl,
-        releases: &releases,
-        readme: &readme,
-        license: &license,
-        contributors: &contributors,
-        owner,
-        repo_name,
-        branch: default_branch,
-        icon_url: &icon_url,
-        repo_gallery,
-    };
+    let asset_matcher = (modules.len() > 1).then(|| AssetMatcher::new(modules));
+
+    let mut plugins: Vec<Plugin> = Vec::new();
+    for (index, module) in modules.iter().enumerate() {
+        let input = PluginBuildInput {
+            repo,
+            dsl: &module.dsl,
+            fallback_name: if modules.len() > 1 && module.module != "root" {
+                module_basename(&module.module)
+            } else {
+                &repo.name
+            },
+            asset_filter: asset_matcher.as_ref().map(|m| (m, index)),
+            releases,
+            readme,
+            license: &license,
+            contributors,
+            owner,
+            repo_name,
+            branch: default_branch,
+            icon_url: &icon_url,
+            repo_gallery: repo_gallery.clone(),
+        };
+
+        let Some(plugin) = build_plugin_from_repo_data(input) else {
+            continue;
+        };
+        if plugins.iter().any(|p| p.id == plugin.id) {
+            debug!(id = %plugin.id, module = %module.module, "Skip: duplicate plugin id in repo");
+            continue;
+        }
+        debug!(id = %plugin.id, module = %module.module, api_version = %plugin.api_version, versions = plugin.versions.len(), "Built plugin");
+        plugins.push(plugin);
+    }
+
+    plugins
+}
 
-    match build_plugin_from_repo_data(input) {
-        Some(plugin) => vec![plugin],
-        None => Vec::new(),
+/// A `Repository` describing a local checkout, as far as it can be known
+/// without the GitHub API.
+fn local_repository(root: &Path) -> Repository {
+    let git_dir = root.join(".git");
+    let remote = fs::read_to_string(git_dir.join("config"))
+        .ok()
+        .and_then(|config| origin_url(&config))
+        .and_then(|url| parse_github_url(&normalize_remote_url(&url)));
+    let branch = fs::read_to_string(git_dir.join("HEAD"))
+        .ok()
+        .and_then(|head| {
+            head.trim()
+                .strip_prefix("ref: refs/heads/")
+                .map(str::to_string)
+        });
+
+    let (owner, name) = remote.unwrap_or_else(|| {
+        let dir = fs::canonicalize(root)
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_else(|| "plugin".to_string());
+        ("local".to_string(), dir)
+    });
+
+    Repository {
+        id: 0,
+        full_name: format!("{}/{}", owner, name),
+        name: name.clone(),
+        description: None,
+        html_url: format!("https://github.com/{}/{}", owner, name),
+        stargazers_count: 0,
+        forks_count: 0,
+        created_at: String::new(),
+        updated_at: String::new(),
+        pushed_at: String::new(),
+        owner: crate::github::Owner {
+            avatar_url: format!("https://github.com/{}.png", owner),
+            html_url: format!("https://github.com/{}", owner),
+            login: owner,
+        },
+        license: None,
+        topics: Vec::new(),
+        is_template: false,
+        fork: false,
+        archived: false,
+        default_branch: branch,
+        contributors_url: String::new(),
+    }
+}
+
+fn origin_url(git_config: &str) -> Option<String> {
+    let mut in_origin = false;
+    for line in git_config.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_origin = line == r#"[remote "origin"]"#;
+        } else if in_origin
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim() == "url"
+        {
+            return Some(value.trim().to_string());
+        }
     }
+    None
+}
+
+/// Turn `git@github.com:o/r.git` and friends into `https://github.com/o/r`.
+fn normalize_remote_url(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    match url.strip_prefix("git@github.com:") {
+        Some(path) => format!("https://github.com/{}", path),
+        None => url.to_string(),
+    }
+}
+
+fn module_basename(module: &str) -> &str {
+    module.rsplit('/').next().unwrap_or(module)
+}
+
+/// Assigns release assets to plugins in multi-plugin repositories.
+///
+/// Each asset goes to the module whose plugin or directory name forms the
+/// longest match inside the asset file name. Between matches of equal
+/// length the one further into the name wins, since artifacts usually start
+/// with the core project's name: `SuiteCore-addon-chat.jar` lands on
+/// `addon-chat` rather than on the `SuiteCore` core module.
+struct AssetMatcher {
+    hints: Vec<Vec<String>>,
+}
+
+impl AssetMatcher {
+    fn new(modules: &[AllayModule]) -> Self {
+        let hints = modules
+            .iter()
+            .map(|m| {
+                let mut hints = vec![normalize_asset_name(module_basename(&m.module))];
+                if let Some(name) = m.dsl.plugin.as_ref().and_then(|p| p.name.as_deref()) {
+                    hints.push(normalize_asset_name(name));
+                }
+                hints.retain(|h| !h.is_empty() && h != "root");
+                hints
+            })
+            .collect();
+        Self { hints }
+    }
+
+    /// Index of the module `asset_name` belongs to. A tie on both length and
+    /// position goes to the first module.
+    fn owner_of(&self, asset_name: &str) -> Option<usize> {
+        let asset = normalize_asset_name(asset_name);
+        self.hints
+            .iter()
+            .enumerate()
+            .filter_map(|(i, hints)| {
+                hints
+                    .iter()
+                    .filter_map(|h| asset.rfind(h.as_str()).map(|start| (h.len(), start)))
+                    .max()
+                    .map(|best| (i, best))
+            })
+            .max_by(|(a, best_a), (b, best_b)| best_a.cmp(best_b).then(b.cmp(a)))
+            .map(|(i, _)| i)
+    }
+}
+
+fn normalize_asset_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
 }
 
 fn build_plugin_from_repo_data(input: PluginBuildInput) -> Option<Plugin> {
     let PluginBuildInput {
         repo,
         dsl,
+        fallback_name,
+        asset_filter,
         releases,
         readme,
         license,
@@ -433,14 +820,17 @@ fn build_plugin_from_repo_data(input: PluginBuildInput) -> Option<Plugin> {
         }
     };
 
-    let plugin_name = plugin_dsl.name.clone().unwrap_or_else(|| repo.name.clone());
+    let plugin_name = plugin_dsl
+        .name
+        .clone()
+        .unwrap_or_else(|| fallback_name.to_string());
 
     let plugin_id = format!("{}/{}", owner, plugin_name).to_lowercase();
 
     let versions: Vec<Version> = releases
         .iter()
         .filter(|r| !r.draft)
-        .map(build_version)
+        .map(|r| build_version(r, asset_filter))
         .filter(|v| !v.files.is_empty())
         .collect();
 
@@ -480,6 +870,12 @@ fn build_plugin_from_repo_data(input: PluginBuildInput) -> Option<Plugin> {
 
     let server_version = dsl.server.clone().unwrap_or_default();
 
+    let allay_gradle_version = dsl
+        .allay_gradle
+        .as_ref()
+        .and_then(|g| g.version.clone())
+        .unwrap_or_default();
+
     let dependencies: Vec<Dependency> = plugin_dsl
         .dependencies
         .iter()
@@ -513,16 +909,18 @@ fn build_plugin_from_repo_data(input: PluginBuildInput) -> Option<Plugin> {
         versions,
         api_version,
         server_version,
+        allay_gradle_version,
         dependencies,
         preserved_fields: Default::default(),
     })
 }
 
-fn build_version(release: &Release) -> Version {
+fn build_version(release: &Release, asset_filter: Option<(&AssetMatcher, usize)>) -> Version {
     let jars: Vec<_> = release
         .assets
         .iter()
         .filter(|a| a.name.ends_with(".jar") || a.name.ends_with(".zip"))
+        .filter(|a| asset_filter.is_none_or(|(m, i)| m.owner_of(&a.name) == Some(i)))
         .collect();
 
     let primary_jar = jars
@@ -601,7 +999,7 @@ fn build_authors(
     authors
 }
 
-fn normalize_version(tag: &str) -> String {
+pub(crate) fn normalize_version(tag: &str) -> String {
     tag.trim_start_matches('v').to_string()
 }
 
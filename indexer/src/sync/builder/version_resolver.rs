@@ -1,20 +1,34 @@
-use crate::github::{GitTreeEntry, client};
-use crate::gradle::VersionRef;
+use super::files::RepoFiles;
+use super::tree_has_file;
+use crate::github::client;
+use crate::gradle::{
+    DEFAULT_CATALOG, GradleProperties, VersionCatalog, VersionCatalogs, VersionRef,
+    settings_catalog_files,
+};
 use std::sync::OnceLock;
 use tracing::debug;
 
 static SNAPSHOT_VERSION_CACHE: OnceLock<Option<String>> = OnceLock::new();
 
+/// Resolve the version of the Allay `artifact` (`api` or `server`) that
+/// `version_ref` points at.
 pub fn resolve_version(
     version_ref: &VersionRef,
-    tree: &[GitTreeEntry],
-    owner: &str,
-    repo: &str,
+    artifact: &str,
+    files: &dyn RepoFiles,
+    props: &GradleProperties,
+    catalogs: &VersionCatalogs,
 ) -> Option<String> {
     let version = match version_ref {
         VersionRef::Literal(v) => Some(v.clone()),
-        VersionRef::VersionCatalog(path) => resolve_version_catalog(path, tree, owner, repo),
-        VersionRef::Variable(var_path) => resolve_variable(var_path, tree, owner, repo),
+        VersionRef::VersionCatalog(path) => catalogs.allay_version(path, artifact),
+        VersionRef::Variable(var_path) => match props.resolve(var_path) {
+            Some(v) => {
+                debug!(variable = var_path, version = v, "Resolved variable from properties");
+                Some(v.to_string())
+            }
+            None => resolve_variable(var_path, files),
+        },
         VersionRef::None => None,
     };
 
@@ -56,63 +70,48 @@ fn extract_api_version_from_name(name: &str) -> Option<String> {
     }
 }
 
-fn resolve_version_catalog(
-    _path: &str,
-    tree: &[GitTreeEntry],
-    owner: &str,
-    repo: &str,
-) -> Option<String> {
-    let toml_path = tree
-        .iter()
-        .find(|e| {
-            e.entry_type == "blob"
-                && (e.path == "gradle/libs.versions.toml" || e.path == "libs.versions.toml")
-        })
-        .map(|e| e.path.as_str())?;
-
-    let content = client().get_file_content(owner, repo, toml_path).ok()?;
-
-    parse_allay_version_from_toml(&content)
-}
-
-fn parse_allay_version_from_toml(content: &str) -> Option<String> {
-    let toml: toml::Value = content.parse().ok()?;
-
-    if let Some(libs) = toml.get("libraries")
-        && let Some(allay) = libs.get("allay")
-            && let Some(version) = allay.get("version") {
-                if let Some(v) = version.as_str() {
-                    return Some(v.to_string());
-                }
-                if let Some(ref_name) = version.get("ref").and_then(|r| r.as_str())
-                    && let Some(versions) = toml.get("versions")
-                        && let Some(v) = versions.get(ref_name).and_then(|v| v.as_str()) {
-                            return Some(v.to_string());
-                        }
-            }
+/// Read the build's version catalogs: `libs` from `gradle/libs.versions.toml`
+/// (or a root `libs.versions.toml`) and any declared in the settings script.
+pub fn load_version_catalogs(files: &dyn RepoFiles) -> VersionCatalogs {
+    let mut sources = Vec::new();
+    if let Some(path) = ["gradle/libs.versions.toml", "libs.versions.toml"]
+        .into_iter()
+        .find(|p| tree_has_file(files.tree(), p))
+    {
+        sources.push((DEFAULT_CATALOG.to_string(), path.to_string()));
+    }
+    for settings in ["settings.gradle.kts", "settings.gradle"] {
+        if tree_has_file(files.tree(), settings)
+            && let Ok(content) = files.read(settings)
+        {
+            sources.extend(settings_catalog_files(&content));
+        }
+    }
 
-    if let Some(versions) = toml.get("versions") {
-        if let Some(v) = versions.get("allay").and_then(|v| v.as_str()) {
-            return Some(v.to_string());
+    let mut catalogs = VersionCatalogs::default();
+    for (name, path) in sources {
+        if !tree_has_file(files.tree(), &path) {
+            continue;
         }
-        if let Some(v) = versions.get("allay-api").and_then(|v| v.as_str()) {
-            return Some(v.to_string());
+        debug!(catalog = %name, file = %path, "Reading version catalog");
+        let parsed = files
+            .read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| VersionCatalog::parse(&content));
+        match parsed {
+            Ok(catalog) => catalogs.insert(&name, catalog),
+            Err(e) => debug!(file = %path, error = %e, "Failed to read version catalog"),
         }
     }
-
-    None
+    catalogs
 }
 
-fn resolve_variable(
-    var_path: &str,
-    tree: &[GitTreeEntry],
-    owner: &str,
-    repo: &str,
-) -> Option<String> {
+fn resolve_variable(var_path: &str, files: &dyn RepoFiles) -> Option<String> {
     let first_part = var_path.split('.').next()?;
     let search_name = first_part.to_lowercase();
 
-    let candidates: Vec<&str> = tree
+    let candidates: Vec<&str> = files
+        .tree()
         .iter()
         .filter(|e| {
             if e.entry_type != "blob" {
@@ -130,8 +129,9 @@ fn resolve_variable(
         .map(|e| e.path.as_str())
         .collect();
 
+    debug!(variable = var_path, candidates = ?candidates, "Searching variable definition");
     for candidate in candidates {
-        if let Ok(content) = client().get_file_content(owner, repo, candidate)
+        if let Ok(content) = files.read(candidate)
             && let Some(version) = extract_allay_version_from_code(&content, var_path) {
                 debug!(file = candidate, version = %version, "Resolved variable version");
                 return Some(version);
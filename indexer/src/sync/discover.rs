@@ -1,5 +1,5 @@
 use super::builder::build_plugins_from_repo;
-use crate::github::client;
+use crate::github::{GitHubError, client};
 use crate::plugin::Plugin;
 use chrono::{Datelike, Utc};
 use std::collections::{HashMap, HashSet};
@@ -31,7 +31,7 @@ const SHARD_LIMIT: u64 = 1000;
 
 pub struct DiscoverResult {
     pub new_plugins: Vec<Plugin>,
-    pub errors: Vec<(String, String)>,
+    pub errors: Vec<(String, GitHubError)>,
 }
 
 pub fn discover_new_plugins(
@@ -302,10 +302,27 @@ fn process_repos_parallel(
     }
 }
 
-fn process_single_repo(repo_match: RepoMatch) -> Result<Vec<Plugin>, String> {
+/// Run the discover pipeline for one repository, bypassing search and the
+/// existing-plugin filter. Used by `inspect`.
+pub fn inspect_repo(full_name: &str) -> Result<Vec<Plugin>, GitHubError> {
+    if let Some(excluded) = EXCLUDED_REPOS
+        .iter()
+        .find(|e| e.eq_ignore_ascii_case(full_name))
+    {
+        debug!(repo = %full_name, rule = %excluded, "Skip excluded");
+        return Ok(Vec::new());
+    }
+
+    process_single_repo(RepoMatch {
+        full_name: full_name.to_string(),
+        gradle_paths: Vec::new(),
+    })
+}
+
+fn process_single_repo(repo_match: RepoMatch) -> Result<Vec<Plugin>, GitHubError> {
     let parts: Vec<&str> = repo_match.full_name.split('/').collect();
     if parts.len() != 2 {
-        return Err("invalid repo name".to_string());
+        return Err(GitHubError::NotFound);
     }
 
     let repo = client().get_repository(parts[0], parts[1])?;
@@ -322,9 +339,14 @@ fn process_single_repo(repo_match: RepoMatch) -> Result<Vec<Plugin>, String> {
         debug!(repo = %repo_match.full_name, "Skip noindex");
         return Ok(Vec::new());
     }
+    debug!(
+        repo = %repo_match.full_name,
+        branch = repo.default_branch.as_deref().unwrap_or("main"),
+        "Repository is eligible"
+    );
 
     let gradle_paths = if repo_match.gradle_paths.is_empty() {
-        find_gradle_files(parts[0], parts[1], &repo)
+        find_gradle_files(parts[0], parts[1], &repo)?
     } else {
         repo_match.gradle_paths
     };
@@ -333,32 +355,38 @@ fn process_single_repo(repo_match: RepoMatch) -> Result<Vec<Plugin>, String> {
         debug!(repo = %repo_match.full_name, "No gradle files found");
         return Ok(Vec::new());
     }
+    debug!(repo = %repo_match.full_name, paths = ?gradle_paths, "Gradle files to check");
 
-    let plugins = build_plugins_from_repo(&repo, &gradle_paths);
+    let plugins = build_plugins_from_repo(&repo, &gradle_paths)?;
     if plugins.is_empty() {
         debug!(repo = %repo_match.full_name, "No plugins found");
     }
     Ok(plugins)
 }
 
-fn find_gradle_files(owner: &str, repo_name: &str, repo: &crate::github::Repository) -> Vec<String> {
+fn find_gradle_files(
+    owner: &str,
+    repo_name: &str,
+    repo: &crate::github::Repository,
+) -> Result<Vec<String>, GitHubError> {
     let branch = repo.default_branch.as_deref().unwrap_or("main");
 
     match client().get_tree(owner, repo_name, branch) {
-        Ok(tree) => {
-            tree.tree
-                .iter()
-                .filter(|entry| {
-                    entry.entry_type == "blob"
-                        && (entry.path.ends_with("build.gradle.kts")
-                            || entry.path.ends_with("build.gradle"))
-                })
-                .map(|entry| entry.path.clone())
-                .collect()
-        }
-        Err(e) => {
-            debug!(repo = %format!("{}/{}", owner, repo_name), error = %e, "Failed to get tree");
-            Vec::new()
+        Ok(tree) => Ok(tree
+            .tree
+            .iter()
+            .filter(|entry| {
+                entry.entry_type == "blob"
+                    && (entry.path.ends_with("build.gradle.kts")
+                        || entry.path.ends_with("build.gradle"))
+            })
+            .map(|entry| entry.path.clone())
+            .collect()),
+        // An empty repository has no tree.
+        Err(GitHubError::NotFound) => {
+            debug!(repo = %format!("{}/{}", owner, repo_name), "No tree");
+            Ok(Vec::new())
         }
+        Err(e) => Err(e),
     }
 }
@@ -0,0 +1,92 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Only decisions made while syncing are part of an explanation; HTTP and
+/// cache bookkeeping is left out.
+const TARGET_PREFIX: &str = "allayindexer::sync";
+
+/// One decision logged while processing a repository.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainStep {
+    pub message: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Collects the `debug!` decisions of the sync code as structured steps, so
+/// `inspect` can show why a repository did or did not produce a plugin
+/// without a separate code path for explaining.
+#[derive(Debug, Clone, Default)]
+pub struct ExplainLayer {
+    steps: Arc<Mutex<Vec<ExplainStep>>>,
+}
+
+impl ExplainLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove and return the steps recorded so far.
+    pub fn take(&self) -> Vec<ExplainStep> {
+        self.steps
+            .lock()
+            .map(|mut s| std::mem::take(&mut *s))
+            .unwrap_or_default()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ExplainLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if !event.metadata().target().starts_with(TARGET_PREFIX) {
+            return;
+        }
+        let mut visitor = StepVisitor::default();
+        event.record(&mut visitor);
+        if let Ok(mut steps) = self.steps.lock() {
+            steps.push(ExplainStep {
+                message: visitor.message,
+                fields: visitor.fields,
+            });
+        }
+    }
+}
+
+#[derive(Default)]
+struct StepVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for StepVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+}
+
+/// The reason a repository produced no plugin: the last skip decision.
+pub fn skip_reason(steps: &[ExplainStep]) -> Option<String> {
+    steps
+        .iter()
+        .rev()
+        .find(|s| s.message.starts_with("Skip") || s.message.starts_with("No "))
+        .map(|s| s.message.clone())
+}
@@ -1,6 +1,11 @@
 pub(crate) mod builder;
 pub mod discover;
+pub mod explain;
+pub mod report;
 pub mod update;
 
-pub use discover::discover_new_plugins;
-pub use update::{UpdateResult, update_existing_plugins};
+pub use builder::build_plugins_from_local;
+pub use discover::{discover_new_plugins, inspect_repo};
+pub use explain::{ExplainLayer, ExplainStep};
+pub use report::RunReport;
+pub use update::{UpdateOptions, UpdateResult, update_existing_plugins};
@@ -0,0 +1,115 @@
+use super::discover::DiscoverResult;
+use super::update::UpdateResult;
+use crate::github::{GitHubError, client};
+use crate::plugin::FieldChange;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Serialize)]
+pub struct PluginChange {
+    pub id: String,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunError {
+    pub id: String,
+    /// `GitHubError::kind`, e.g. `rate_limited`.
+    pub kind: &'static str,
+    pub error: String,
+}
+
+/// What an `update` or `discover` run did, written by `--report` so CI can
+/// summarize a run without scraping the log.
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    pub command: &'static str,
+    pub dry_run: bool,
+    pub added: Vec<String>,
+    pub updated: Vec<PluginChange>,
+    pub deleted: Vec<String>,
+    /// Plugins found missing but kept until the deletion policy confirms
+    /// them.
+    pub missing: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub errors: Vec<RunError>,
+    pub api_calls: usize,
+    pub cache_hits: usize,
+    pub rate_limit_remaining: usize,
+}
+
+impl RunReport {
+    pub fn from_update(result: &UpdateResult, dry_run: bool) -> Self {
+        let updated = result
+            .updated
+            .iter()
+            .map(|plugin| PluginChange {
+                id: plugin.id.clone(),
+                changes: result.changes.get(&plugin.id).cloned().unwrap_or_default(),
+            })
+            .collect();
+
+        let mut report = Self::new("update", dry_run);
+        report.updated = updated;
+        report.deleted = result.deleted.clone();
+        report.missing = result.missing.clone();
+        report.unchanged = result.unchanged.clone();
+        report.errors = run_errors(&result.errors);
+        report.sort();
+        report
+    }
+
+    pub fn from_discover(result: &DiscoverResult, dry_run: bool) -> Self {
+        let mut report = Self::new("discover", dry_run);
+        report.added = result.new_plugins.iter().map(|p| p.id.clone()).collect();
+        report.errors = run_errors(&result.errors);
+        report.sort();
+        report
+    }
+
+    /// A report of a run that had nothing to do.
+    pub fn new(command: &'static str, dry_run: bool) -> Self {
+        Self {
+            command,
+            dry_run,
+            added: Vec::new(),
+            updated: Vec::new(),
+            deleted: Vec::new(),
+            missing: Vec::new(),
+            unchanged: Vec::new(),
+            errors: Vec::new(),
+            api_calls: client().api_calls(),
+            cache_hits: client().cache_hits(),
+            rate_limit_remaining: client().rate_limit.remaining(),
+        }
+    }
+
+    /// Results arrive in completion order; sort them so reports of the same
+    /// run compare equal.
+    fn sort(&mut self) {
+        self.added.sort();
+        self.updated.sort_by(|a, b| a.id.cmp(&b.id));
+        self.deleted.sort();
+        self.missing.sort();
+        self.unchanged.sort();
+        self.errors.sort_by(|a, b| a.id.cmp(&b.id));
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize report: {}", e))?;
+        fs::write(path, json + "\n").map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+}
+
+fn run_errors(errors: &[(String, GitHubError)]) -> Vec<RunError> {
+    errors
+        .iter()
+        .map(|(id, error)| RunError {
+            id: id.clone(),
+            kind: error.kind(),
+            error: error.to_string(),
+        })
+        .collect()
+}
@@ -1,51 +1,140 @@
-use super::builder::{build_plugins_from_repo, parse_github_url};
-use crate::github::client;
-use crate::plugin::Plugin;
+use super::builder::{build_plugins_from_repo, normalize_version, parse_github_url, parse_timestamp};
+use crate::cache::{BUILDER_VERSION, RepoRevision};
+use crate::github::{GitHubError, Release, Repository, client};
+use crate::plugin::{DeletionPolicy, FieldChange, MissingReason, Plugin, Tombstones, diff_plugins};
 use std::collections::{HashMap, HashSet};
 use tracing::{debug, debug_span, info, warn};
 
+#[derive(Debug, Clone, Default)]
+pub struct UpdateOptions {
+    /// Rewrite every plugin, even when nothing changed, and rebuild it from
+    /// the repository even when its revision is unchanged.
+    pub force: bool,
+    /// Fields whose changes alone don't make a plugin updated, e.g. `stars`
+    /// and `downloads`. A name matches a top-level field or the last segment
+    /// of a nested path, so `downloads` also covers per-version counts.
+    pub ignored_fields: Vec<String>,
+    /// Revisions plugins were last built from, keyed by id. A plugin whose
+    /// repository is still at that revision only gets its counters refreshed.
+    pub revisions: HashMap<String, RepoRevision>,
+    /// Plugins earlier runs found missing.
+    pub tombstones: Tombstones,
+    /// When a missing plugin is actually deleted.
+    pub deletion: DeletionPolicy,
+    /// Most plugins one run may delete; the rest wait for the next run.
+    pub max_deletions: Option<usize>,
+}
+
 pub struct UpdateResult {
     pub updated: Vec<Plugin>,
+    /// Field-level changes of each updated plugin, keyed by id.
+    pub changes: HashMap<String, Vec<FieldChange>>,
     pub unchanged: Vec<String>,
+    /// Missing plugins the deletion policy confirmed.
     pub deleted: Vec<String>,
-    pub errors: Vec<(String, String)>,
+    /// Missing plugins still within their grace period or held back by
+    /// `max_deletions`.
+    pub missing: Vec<String>,
+    pub errors: Vec<(String, GitHubError)>,
     pub processed_ids: HashSet<String>,
+    /// Revisions of the updated and unchanged plugins, to pass back in
+    /// `UpdateOptions::revisions` on the next run.
+    pub revisions: HashMap<String, RepoRevision>,
+    /// `UpdateOptions::tombstones` after this run, without the deleted
+    /// plugins and those found again.
+    pub tombstones: Tombstones,
     pub stopped_by_rate_limit: bool,
 }
 
-pub fn update_existing_plugins(plugins: &[Plugin], force: bool) -> UpdateResult {
+pub fn update_existing_plugins(plugins: &[Plugin], options: &UpdateOptions) -> UpdateResult {
     if plugins.is_empty() {
         return UpdateResult {
             updated: Vec::new(),
+            changes: HashMap::new(),
             unchanged: Vec::new(),
             deleted: Vec::new(),
+            missing: Vec::new(),
             errors: Vec::new(),
             processed_ids: HashSet::new(),
+            revisions: HashMap::new(),
+            tombstones: options.tombstones.clone(),
             stopped_by_rate_limit: false,
         };
     }
 
-    let batch = client().execute_parallel(plugins.to_vec(), move |plugin, _| {
+    // One GraphQL query per batch of repositories replaces the per-plugin
+    // repository, release and README requests.
+    let mut repos: Vec<(String, String)> = plugins
+        .iter()
+        .filter_map(|p| parse_github_url(&p.source))
+        .collect();
+    repos.sort();
+    repos.dedup();
+    client().prefetch_repositories(&repos);
+
+    let batch = client().execute_parallel(plugins.to_vec(), |plugin, _| {
         let _span = debug_span!("update_plugin", id = %plugin.id).entered();
-        (plugin.id.clone(), update_plugin(&plugin, force))
+        (plugin.id.clone(), update_plugin(&plugin, options))
     });
 
     let mut updated = Vec::new();
+    let mut changes = HashMap::new();
     let mut unchanged = Vec::new();
     let mut deleted = Vec::new();
+    let mut missing = Vec::new();
     let mut errors = Vec::new();
     let mut processed_ids = HashSet::new();
+    let mut revisions = HashMap::new();
+    let mut tombstones = options.tombstones.clone();
+    let today = chrono::Utc::now().date_naive();
 
     for (id, status) in batch.results {
         processed_ids.insert(id.clone());
+        if matches!(status, Ok(UpdateStatus::Updated(..) | UpdateStatus::Unchanged(_)))
+            && tombstones.remove(&id)
+        {
+            info!(id = %id, "Missing plugin found again");
+        }
         match status {
-            Ok(UpdateStatus::Updated(plugin)) => updated.push(*plugin),
-            Ok(UpdateStatus::Unchanged) => unchanged.push(id),
-            Ok(UpdateStatus::Deleted) => deleted.push(id),
+            Ok(UpdateStatus::Updated(plugin, diff, revision)) => {
+                if let Some(revision) = revision {
+                    revisions.insert(id.clone(), revision);
+                }
+                changes.insert(id, diff);
+                updated.push(*plugin);
+            }
+            Ok(UpdateStatus::Unchanged(revision)) => {
+                if let Some(revision) = revision {
+                    revisions.insert(id.clone(), revision);
+                }
+                unchanged.push(id);
+            }
+            Ok(UpdateStatus::Deleted(reason)) => {
+                if tombstones.mark(&id, reason, today, &options.deletion) {
+                    deleted.push(id);
+                } else {
+                    debug!(id = %id, reason = ?reason, "Plugin missing, keeping it for now");
+                    missing.push(id);
+                }
+            }
             Err(e) => errors.push((id, e)),
         }
     }
 
+    if let Some(max) = options.max_deletions
+        && deleted.len() > max
+    {
+        warn!(
+            confirmed = deleted.len(),
+            max = max,
+            "Too many deletions, deferring the rest to the next run"
+        );
+        missing.extend(deleted.split_off(max));
+    }
+    for id in &deleted {
+        tombstones.remove(id);
+    }
+
     info!(
         processed = batch.processed,
         total = batch.total,
@@ -63,67 +152,170 @@ pub fn update_existing_plugins(plugins: &[Plugin], force: bool) -> UpdateResult
 
     UpdateResult {
         updated,
+        changes,
         unchanged,
         deleted,
+        missing,
         errors,
         processed_ids,
+        revisions,
+        tombstones,
         stopped_by_rate_limit: batch.stopped_by_rate_limit,
     }
 }
 
 #[derive(Debug)]
 enum UpdateStatus {
-    Updated(Box<Plugin>),
-    Unchanged,
-    Deleted,
+    Updated(Box<Plugin>, Vec<FieldChange>, Option<RepoRevision>),
+    Unchanged(Option<RepoRevision>),
+    Deleted(MissingReason),
 }
 
-fn update_plugin(plugin: &Plugin, force: bool) -> Result<UpdateStatus, String> {
+fn update_plugin(plugin: &Plugin, options: &UpdateOptions) -> Result<UpdateStatus, GitHubError> {
     let (owner, repo_name) = match parse_github_url(&plugin.source) {
         Some(parts) => parts,
-        None => return Ok(UpdateStatus::Unchanged),
+        None => return Ok(UpdateStatus::Unchanged(None)),
     };
 
     let repo = match client().get_repository(&owner, &repo_name) {
         Ok(r) => r,
-        Err(e) if e.contains("404") => {
+        Err(GitHubError::NotFound) => {
             debug!(id = %plugin.id, "Plugin repo not found, marking deleted");
-            return Ok(UpdateStatus::Deleted);
+            return Ok(UpdateStatus::Deleted(MissingReason::NotFound));
         }
         Err(e) => return Err(e),
     };
 
     if repo.archived {
         debug!(id = %plugin.id, "Plugin repo archived, marking deleted");
-        return Ok(UpdateStatus::Deleted);
+        return Ok(UpdateStatus::Deleted(MissingReason::Archived));
     }
     if repo.topics.iter().any(|t| t == "noindex") {
         debug!(id = %plugin.id, "Plugin has noindex topic, marking deleted");
-        return Ok(UpdateStatus::Deleted);
+        return Ok(UpdateStatus::Deleted(MissingReason::NoIndex));
     }
 
-    let new_plugins = build_plugins_from_repo(&repo, &[]);
-
-    let new_plugin = new_plugins.into_iter().find(|p| p.id == plugin.id);
+    let releases = client().get_releases(&owner, &repo_name).ok();
+    let revision = releases
+        .as_deref()
+        .and_then(|r| current_revision(&owner, &repo_name, &repo, r));
+    let unchanged_revision = !options.force
+        && revision.is_some()
+        && options.revisions.get(&plugin.id) == revision.as_ref();
 
-    let mut new_plugin = match new_plugin {
-        Some(p) => p,
-        None => {
-            debug!(id = %plugin.id, "Plugin no longer in repo, marking deleted");
-            return Ok(UpdateStatus::Deleted);
+    let mut new_plugin = if unchanged_revision {
+        debug!(id = %plugin.id, "Revision unchanged, refreshing counters only");
+        refresh_stats(plugin, &repo, releases.as_deref().unwrap_or_default())
+    } else {
+        let new_plugins = build_plugins_from_repo(&repo, &[])?;
+        match new_plugins.into_iter().find(|p| p.id == plugin.id) {
+            Some(p) => p,
+            None => {
+                debug!(id = %plugin.id, "Plugin no longer in repo, marking deleted");
+                return Ok(UpdateStatus::Deleted(MissingReason::ModuleRemoved));
+            }
         }
     };
 
     merge_preserved_fields(plugin, &mut new_plugin);
     merge_gallery_created(plugin, &mut new_plugin);
 
-    if force || plugin_changed(plugin, &new_plugin) {
-        Ok(UpdateStatus::Updated(Box::new(new_plugin)))
+    let changes = diff_plugins(plugin, &new_plugin);
+    let significant = changes
+        .iter()
+        .filter(|c| !is_ignored(c, &options.ignored_fields))
+        .count();
+    for change in &changes {
+        debug!(
+            id = %plugin.id,
+            path = %change.path,
+            old = %change.old,
+            new = %change.new,
+            "Field changed"
+        );
+    }
+
+    if options.force || significant > 0 {
+        Ok(UpdateStatus::Updated(Box::new(new_plugin), changes, revision))
     } else {
-        Ok(UpdateStatus::Unchanged)
+        if !changes.is_empty() {
+            debug!(id = %plugin.id, changes = changes.len(), "Only ignored fields changed");
+        }
+        Ok(UpdateStatus::Unchanged(revision))
     }
 }
 
+/// The default-branch tree and release ids the repository is at now. The
+/// tree comes from the GraphQL prefetch or is revalidated with its ETag, so
+/// this is cheap compared to a rebuild.
+fn current_revision(
+    owner: &str,
+    repo_name: &str,
+    repo: &Repository,
+    releases: &[Release],
+) -> Option<RepoRevision> {
+    let branch = repo.default_branch.as_deref().unwrap_or("main");
+    let tree = client().get_tree(owner, repo_name, branch).ok()?;
+
+    let mut release_ids: Vec<u64> = releases.iter().map(|r| r.id).collect();
+    release_ids.sort_unstable();
+    Some(RepoRevision {
+        tree_sha: tree.sha,
+        release_ids,
+        metadata_hash: metadata_hash(repo),
+        builder_version: BUILDER_VERSION,
+    })
+}
+
+/// FNV-1a over the repository metadata the builder copies into a plugin.
+/// Stable across runs and toolchains, unlike `DefaultHasher`.
+fn metadata_hash(repo: &Repository) -> u64 {
+    let license = repo.license.as_ref();
+    let fields = [
+        repo.description.as_deref().unwrap_or_default(),
+        &repo.topics.join(","),
+        license.map_or("", |l| l.spdx_id.as_str()),
+        license.map_or("", |l| l.name.as_str()),
+        license.and_then(|l| l.html_url.as_deref()).unwrap_or_default(),
+        &repo.owner.avatar_url,
+    ];
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for field in fields {
+        for byte in field.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Copy of `old` with stars, download counts and the update time taken from
+/// the repository and its releases; everything else stays as last built.
+/// Metadata the builder derives more from is part of `RepoRevision`.
+fn refresh_stats(old: &Plugin, repo: &Repository, releases: &[Release]) -> Plugin {
+    let mut plugin = old.clone();
+    plugin.stars = repo.stargazers_count;
+    plugin.updated_at = parse_timestamp(&repo.updated_at);
+
+    for version in &mut plugin.versions {
+        let Some(release) = releases
+            .iter()
+            .find(|r| normalize_version(&r.tag_name) == version.version)
+        else {
+            continue;
+        };
+        version.downloads = release
+            .assets
+            .iter()
+            .filter(|a| version.files.iter().any(|f| f.filename == a.name))
+            .map(|a| a.download_count)
+            .sum();
+    }
+    plugin.downloads = plugin.versions.iter().map(|v| v.downloads).sum();
+
+    plugin
+}
+
 fn merge_preserved_fields(old: &Plugin, new: &mut Plugin) {
     if old.preserved_fields.is_empty() {
         return;
@@ -164,26 +356,97 @@ fn merge_gallery_created(old: &Plugin, new: &mut Plugin) {
     }
 }
 
-fn plugin_changed(old: &Plugin, new: &Plugin) -> bool {
-    old.name != new.name
-        || old.summary != new.summary
-        || old.stars != new.stars
-        || old.downloads != new.downloads
-        || old.license != new.license
-        || old.authors != new.authors
-        || versions_changed(&old.versions, &new.versions)
+fn is_ignored(change: &FieldChange, ignored_fields: &[String]) -> bool {
+    let leaf = change.path.rsplit('.').next().unwrap_or_default();
+    ignored_fields
+        .iter()
+        .any(|f| f == change.field() || f == leaf)
 }
 
-fn versions_changed(old: &[crate::plugin::Version], new: &[crate::plugin::Version]) -> bool {
-    if old.len() != new.len() {
-        return true;
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
 
-    for (o, n) in old.iter().zip(new.iter()) {
-        if o.version != n.version || o.downloads != n.downloads {
-            return true;
+    fn change(path: &str) -> FieldChange {
+        FieldChange {
+            path: path.to_string(),
+            old: Value::Null,
+            new: Value::Null,
         }
     }
 
-    false
+    #[test]
+    fn test_refresh_stats() {
+        let old: Plugin = serde_json::from_value(serde_json::json!({
+            "id": "example/helloallay",
+            "name": "HelloAllay",
+            "source": "https://github.com/example/hello-allay",
+            "api_version": "0.23.0",
+            "stars": 3,
+            "downloads": 5,
+            "versions": [{
+                "version": "1.2.0",
+                "name": "1.2.0",
+                "downloads": 5,
+                "files": [{ "filename": "HelloAllay.jar", "url": "", "size": 1, "primary": true }]
+            }]
+        }))
+        .unwrap();
+        let repo: Repository = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "full_name": "example/hello-allay",
+            "name": "hello-allay",
+            "stargazers_count": 12,
+            "updated_at": "2024-05-01T00:00:00Z",
+            "owner": { "login": "example" }
+        }))
+        .unwrap();
+        let releases: Vec<Release> = serde_json::from_value(serde_json::json!([{
+            "id": 2,
+            "tag_name": "v1.2.0",
+            "name": null,
+            "assets": [
+                { "id": 3, "name": "HelloAllay.jar", "download_count": 30 },
+                { "id": 4, "name": "sources.zip", "download_count": 99 }
+            ]
+        }]))
+        .unwrap();
+
+        let plugin = refresh_stats(&old, &repo, &releases);
+        assert_eq!(plugin.stars, 12);
+        assert_eq!(plugin.versions[0].downloads, 30);
+        assert_eq!(plugin.downloads, 30);
+        assert_eq!(plugin.api_version, "0.23.0");
+        assert_eq!(plugin.updated_at, parse_timestamp("2024-05-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_metadata_hash() {
+        let mut repo: Repository = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "full_name": "example/hello-allay",
+            "name": "hello-allay",
+            "description": "Says hello",
+            "topics": ["allaymc-plugin"],
+            "owner": { "login": "example" }
+        }))
+        .unwrap();
+        let hash = metadata_hash(&repo);
+        repo.stargazers_count = 40;
+        assert_eq!(metadata_hash(&repo), hash);
+        repo.topics.push("utility".to_string());
+        assert_ne!(metadata_hash(&repo), hash);
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        let ignored = vec!["stars".to_string(), "downloads".to_string()];
+        assert!(is_ignored(&change("stars"), &ignored));
+        assert!(is_ignored(&change("downloads"), &ignored));
+        assert!(is_ignored(&change("versions[1.0].downloads"), &ignored));
+        assert!(!is_ignored(&change("versions[1.1]"), &ignored));
+        assert!(!is_ignored(&change("description"), &ignored));
+        assert!(!is_ignored(&change("stars"), &[]));
+    }
 }
@@ -1,5 +1,8 @@
+use crate::plugin::Plugin;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fs;
+use std::path::Path;
 
 pub fn has_flag(args: &[String], flag: &str) -> bool {
     args.iter().any(|a| a == flag)
@@ -51,3 +54,30 @@ pub fn write_processed_ids(ids: &HashSet<String>) {
 pub fn clear_processed_ids() {
     let _ = fs::remove_file(".update_progress");
 }
+
+const SESSION_FILE: &str = "session.json";
+
+/// Run parameters stored next to a `--record` bundle so `--replay` issues the
+/// same requests: the incremental discover date and the plugins an update
+/// covered, as they were in the index, so a replay doesn't depend on the
+/// local checkout of it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecordSession {
+    pub command: String,
+    #[serde(default)]
+    pub last_sync: Option<String>,
+    #[serde(default)]
+    pub plugins: Vec<Plugin>,
+}
+
+pub fn read_record_session(dir: &str) -> Option<RecordSession> {
+    let content = fs::read_to_string(Path::new(dir).join(SESSION_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn write_record_session(dir: &str, session: &RecordSession) {
+    let _ = fs::create_dir_all(dir);
+    if let Ok(json) = serde_json::to_string_pretty(session) {
+        let _ = fs::write(Path::new(dir).join(SESSION_FILE), json);
+    }
+}
@@ -0,0 +1,292 @@
+use allayindexer::github::{FixtureTransport, GitHubClient, init_client_with};
+use allayindexer::plugin::{DeletionPolicy, MissingReason, Plugin, Tombstones};
+use allayindexer::sync::{RunReport, UpdateOptions, discover_new_plugins, update_existing_plugins};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Once};
+
+static INIT: Once = Once::new();
+
+fn init_fixture_client() {
+    INIT.call_once(|| {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/basic");
+        let mut client = GitHubClient::new(None);
+        client.set_transport(Arc::new(FixtureTransport::new(dir)));
+        init_client_with(client).unwrap();
+    });
+}
+
+fn indexed_plugin() -> Plugin {
+    serde_json::from_value(serde_json::json!({
+        "id": "example/helloallay",
+        "name": "HelloAllay",
+        "source": "https://github.com/example/hello-allay",
+        "summary": "A friendly greeting plugin",
+        "stars": 3
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_update_from_fixtures() {
+    init_fixture_client();
+
+    let result = update_existing_plugins(&[indexed_plugin()], &UpdateOptions::default());
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert!(result.deleted.is_empty());
+    assert_eq!(result.updated.len(), 1);
+
+    let plugin = &result.updated[0];
+    assert_eq!(plugin.id, "example/helloallay");
+    assert_eq!(plugin.stars, 12);
+    assert_eq!(plugin.api_version, "0.23.0");
+    assert_eq!(plugin.categories, vec!["social"]);
+    assert_eq!(plugin.license.id, "MIT");
+    assert_eq!(plugin.downloads, 42);
+    assert_eq!(plugin.versions.len(), 2);
+    assert_eq!(plugin.versions[0].version, "1.2.0");
+    assert_eq!(
+        plugin
+            .authors
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["example", "helper"]
+    );
+    assert_eq!(
+        plugin.gallery[0].url,
+        "https://raw.githubusercontent.com/example/hello-allay/main/docs/gallery1.png"
+    );
+    assert!(
+        plugin
+            .description
+            .contains("https://github.com/example/hello-allay/blob/main/docs/usage.md")
+    );
+}
+
+/// Move every tombstone's latest confirmation to an earlier day, as if the
+/// next run happened the day after.
+fn seen_yesterday(tombstones: Tombstones) -> Tombstones {
+    let mut json = serde_json::to_value(&tombstones).unwrap();
+    for tombstone in json.as_object_mut().unwrap().values_mut() {
+        tombstone["last_seen"] = "2000-01-01".into();
+    }
+    serde_json::from_value(json).unwrap()
+}
+
+#[test]
+fn test_update_missing_repo_is_deleted() {
+    init_fixture_client();
+
+    let mut plugin = indexed_plugin();
+    plugin.id = "example/gone".to_string();
+    plugin.source = "https://github.com/example/gone".to_string();
+
+    // The first days only tombstone it.
+    let mut options = UpdateOptions::default();
+    for run in 1..DeletionPolicy::default().runs {
+        let result = update_existing_plugins(std::slice::from_ref(&plugin), &options);
+        assert!(result.deleted.is_empty());
+        assert_eq!(result.missing, vec!["example/gone"]);
+        let tombstone = result.tombstones.get("example/gone").unwrap();
+        assert_eq!(tombstone.reason, MissingReason::NotFound);
+        assert_eq!(tombstone.confirmations, run);
+
+        // Another run the same day doesn't count.
+        options.tombstones = result.tombstones;
+        let result = update_existing_plugins(std::slice::from_ref(&plugin), &options);
+        assert!(result.deleted.is_empty());
+        assert_eq!(result.tombstones.get("example/gone").unwrap().confirmations, run);
+        options.tombstones = seen_yesterday(result.tombstones);
+    }
+
+    let result = update_existing_plugins(&[plugin], &options);
+
+    assert_eq!(result.deleted, vec!["example/gone"]);
+    assert!(result.tombstones.is_empty());
+}
+
+#[test]
+fn test_max_deletions_defers_the_rest() {
+    init_fixture_client();
+
+    let plugins: Vec<Plugin> = ["gone", "gone-too"]
+        .iter()
+        .map(|name| {
+            let mut plugin = indexed_plugin();
+            plugin.id = format!("example/{}", name);
+            plugin.source = format!("https://github.com/example/{}", name);
+            plugin
+        })
+        .collect();
+    let options = UpdateOptions {
+        deletion: DeletionPolicy { runs: 1, days: 7 },
+        max_deletions: Some(1),
+        ..Default::default()
+    };
+
+    let result = update_existing_plugins(&plugins, &options);
+
+    assert_eq!(result.deleted, vec!["example/gone"]);
+    assert_eq!(result.missing, vec!["example/gone-too"]);
+    assert!(result.tombstones.get("example/gone-too").is_some());
+}
+
+#[test]
+fn test_update_unchanged_revision_refreshes_stats_only() {
+    init_fixture_client();
+
+    let first = update_existing_plugins(&[indexed_plugin()], &UpdateOptions::default());
+    assert!(first.revisions.contains_key("example/helloallay"));
+
+    // A summary edited by hand survives because nothing is rebuilt.
+    let mut plugin = first.updated[0].clone();
+    plugin.summary = "Edited".to_string();
+    plugin.stars = 3;
+    let options = UpdateOptions {
+        revisions: first.revisions.clone(),
+        ..Default::default()
+    };
+    let second = update_existing_plugins(&[plugin], &options);
+
+    assert_eq!(second.updated.len(), 1);
+    assert_eq!(second.updated[0].summary, "Edited");
+    assert_eq!(second.updated[0].stars, 12);
+    let changes = &second.changes["example/helloallay"];
+    assert_eq!(changes.len(), 1, "{:?}", changes);
+    assert_eq!(second.revisions, first.revisions);
+}
+
+#[test]
+fn test_blob_cache_serves_repeated_reads() {
+    use allayindexer::github::client;
+
+    init_fixture_client();
+
+    let first = client()
+        .get_blob("example", "hello-allay", "build.gradle.kts", "a1")
+        .unwrap();
+    let hits = client().cache_hits();
+    // No fixture exists for this path, so only the cache can answer.
+    let second = client()
+        .get_blob("example", "hello-allay", "other/path", "a1")
+        .unwrap();
+
+    assert_eq!(first, second);
+    assert!(client().cache_hits() > hits);
+    assert!(client().export_data_cache().blobs.contains_key("a1"));
+}
+
+#[test]
+fn test_update_report_lists_field_changes() {
+    init_fixture_client();
+
+    let previous = vec![indexed_plugin()];
+    let result = update_existing_plugins(&previous, &UpdateOptions::default());
+    let report = serde_json::to_value(RunReport::from_update(&result, true)).unwrap();
+
+    assert_eq!(report["command"], "update");
+    assert_eq!(report["updated"][0]["id"], "example/helloallay");
+    let changes = report["updated"][0]["changes"].as_array().unwrap();
+    let stars = changes.iter().find(|c| c["path"] == "stars").unwrap();
+    assert_eq!(stars["old"], 3);
+    assert_eq!(stars["new"], 12);
+    assert!(changes.iter().any(|c| c["path"] == "versions[1.2.0]"));
+}
+
+#[test]
+fn test_discover_from_fixtures() {
+    init_fixture_client();
+
+    let result = discover_new_plugins(&HashSet::new(), &HashSet::new(), None);
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let ids: Vec<_> = result.new_plugins.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(ids, vec!["example/helloallay"]);
+}
+
+#[test]
+fn test_multi_plugin_repo_emits_one_plugin_per_module() {
+    init_fixture_client();
+
+    let indexed: Vec<Plugin> = ["example/suitecore", "example/suitechat"]
+        .iter()
+        .map(|id| {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "name": id,
+                "source": "https://github.com/example/allay-suite"
+            }))
+            .unwrap()
+        })
+        .collect();
+
+    let result = update_existing_plugins(&indexed, &UpdateOptions::default());
+
+    assert!(result.deleted.is_empty(), "{:?}", result.deleted);
+    let mut updated = result.updated;
+    updated.sort_by(|a, b| a.id.cmp(&b.id));
+    assert_eq!(updated.len(), 2);
+
+    let chat = &updated[0];
+    assert_eq!(chat.id, "example/suitechat");
+    assert_eq!(chat.dependencies[0].plugin_id, "suitecore");
+    assert_eq!(chat.versions[0].files.len(), 1);
+    assert_eq!(
+        chat.versions[0].files[0].filename,
+        "SuiteCore-addon-chat-1.0.0.jar"
+    );
+    assert_eq!(chat.downloads, 4);
+
+    let core = &updated[1];
+    assert_eq!(core.id, "example/suitecore");
+    assert_eq!(core.versions[0].files[0].filename, "SuiteCore-1.0.0.jar");
+    assert_eq!(core.downloads, 9);
+}
+
+#[test]
+fn test_inspect_explains_decisions() {
+    use allayindexer::sync::{ExplainLayer, inspect_repo};
+    use tracing_subscriber::prelude::*;
+
+    init_fixture_client();
+
+    let explain = ExplainLayer::new();
+    let subscriber = tracing_subscriber::registry().with(explain.clone());
+    let plugins = tracing::subscriber::with_default(subscriber, || {
+        inspect_repo("example/hello-allay").unwrap()
+    });
+
+    assert_eq!(plugins.len(), 1);
+    let messages: Vec<String> = explain.take().into_iter().map(|s| s.message).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Repository is eligible",
+            "Gradle files to check",
+            "Found plugin module",
+            "Built plugin"
+        ]
+    );
+}
+
+#[test]
+fn test_inspect_excluded_repo() {
+    use allayindexer::sync::explain::skip_reason;
+    use allayindexer::sync::{ExplainLayer, inspect_repo};
+    use tracing_subscriber::prelude::*;
+
+    init_fixture_client();
+
+    let explain = ExplainLayer::new();
+    let subscriber = tracing_subscriber::registry().with(explain.clone());
+    let plugins =
+        tracing::subscriber::with_default(subscriber, || inspect_repo("AllayMC/Allay").unwrap());
+
+    assert!(plugins.is_empty());
+    assert_eq!(
+        skip_reason(&explain.take()).as_deref(),
+        Some("Skip excluded")
+    );
+}
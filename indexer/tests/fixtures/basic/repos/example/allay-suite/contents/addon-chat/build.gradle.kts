@@ -0,0 +1,3 @@
+dependencies {
+    compileOnly("org.allaymc.allay:api:0.23.0")
+}
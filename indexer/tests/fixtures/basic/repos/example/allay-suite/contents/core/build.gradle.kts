@@ -0,0 +1,12 @@
+plugins {
+    id("org.allaymc.gradle.plugin") version "0.2.1"
+}
+
+allay {
+    api = "0.23.0"
+    plugin {
+        entrance = ".SuiteCore"
+        name = "SuiteCore"
+        authors += "example"
+    }
+}
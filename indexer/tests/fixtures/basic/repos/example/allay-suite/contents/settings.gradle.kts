@@ -0,0 +1,2 @@
+rootProject.name = "AllaySuite"
+include("api", "core", "addon-chat")
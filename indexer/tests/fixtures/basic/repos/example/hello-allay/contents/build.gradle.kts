@@ -0,0 +1,18 @@
+plugins {
+    id("java-library")
+    id("org.allaymc.gradle.plugin") version "0.2.1"
+}
+
+group = "org.example"
+version = "1.2.0"
+
+allay {
+    api = "0.23.0"
+    plugin {
+        entrance = ".HelloAllay"
+        name = "HelloAllay"
+        authors += "example"
+        authors += "helper"
+        website = "https://example.org/hello"
+    }
+}
@@ -0,0 +1,229 @@
+use allayindexer::sync::build_plugins_from_local;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A temporary checkout of `remote` on `main`, holding only `.git`.
+fn checkout(name: &str, remote: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("allayindexer-local-{}-{}", name, std::process::id()));
+    fs::create_dir_all(dir.join(".git")).unwrap();
+    fs::write(
+        dir.join(".git/config"),
+        format!("[remote \"origin\"]\n\turl = {}\n", remote),
+    )
+    .unwrap();
+    fs::write(dir.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+    dir
+}
+
+/// Write `content` to `path` in `dir`, creating parent directories.
+fn write(dir: &Path, path: &str, content: &str) {
+    let path = dir.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_build_plugins_from_local_checkout() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/basic");
+    let dir = checkout("hello", "git@github.com:example/hello-allay.git");
+    write(&dir, ".git/HEAD", "ref: refs/heads/dev\n");
+    fs::copy(
+        fixtures.join("repos/example/hello-allay/contents/build.gradle.kts"),
+        dir.join("build.gradle.kts"),
+    )
+    .unwrap();
+    fs::copy(
+        fixtures.join("repos/example/hello-allay/readme"),
+        dir.join("README.md"),
+    )
+    .unwrap();
+    write(&dir, "docs/gallery1.png", "");
+    write(&dir, "build/libs/logo.png", "");
+
+    let result = build_plugins_from_local(&dir);
+    let _ = fs::remove_dir_all(&dir);
+
+    let plugins = result.unwrap();
+    assert_eq!(plugins.len(), 1);
+    let plugin = &plugins[0];
+    assert_eq!(plugin.id, "example/helloallay");
+    assert_eq!(plugin.source, "https://github.com/example/hello-allay");
+    assert_eq!(plugin.api_version, "0.23.0");
+    assert_eq!(
+        plugin.gallery[0].url,
+        "https://raw.githubusercontent.com/example/hello-allay/dev/docs/gallery1.png"
+    );
+    // Build output is not part of the checkout.
+    assert_eq!(plugin.icon_url, "https://github.com/example.png");
+    assert!(plugin.versions.is_empty());
+}
+
+#[test]
+fn test_build_plugins_from_local_without_plugin() {
+    let dir = std::env::temp_dir().join(format!("allayindexer-local-empty-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("build.gradle.kts"), "plugins { java }\n").unwrap();
+
+    let result = build_plugins_from_local(&dir);
+    let _ = fs::remove_dir_all(&dir);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_build_plugins_from_local_with_gradle_properties() {
+    let dir = checkout("props", "https://github.com/example/props-plugin.git");
+    write(&dir, "gradle.properties", "allayVersion=0.20.0\npluginName=PropsPlugin\n");
+    write(&dir, "plugin/gradle.properties", "allayVersion = 0.25.0\n");
+    write(
+        &dir,
+        "plugin/build.gradle",
+        r#"
+dependencies {
+    compileOnly('org.allaymc.allay', 'api', allayVersion)
+}
+
+allay {
+    plugin {
+        entrance = '.PropsPlugin'
+        name = "${pluginName}"
+    }
+}
+"#,
+    );
+
+    let result = build_plugins_from_local(&dir);
+    let _ = fs::remove_dir_all(&dir);
+
+    let plugins = result.unwrap();
+    assert_eq!(plugins.len(), 1);
+    assert_eq!(plugins[0].api_version, "0.25.0");
+    assert_eq!(plugins[0].name, "PropsPlugin");
+}
+
+#[test]
+fn test_build_plugins_from_local_with_custom_catalog() {
+    let dir = checkout("catalog", "https://github.com/example/catalog-plugin.git");
+    write(
+        &dir,
+        "settings.gradle.kts",
+        r#"
+rootProject.name = "CatalogPlugin"
+
+dependencyResolutionManagement {
+    versionCatalogs {
+        create("deps") {
+            from(files("gradle/deps.versions.toml"))
+        }
+    }
+}
+"#,
+    );
+    write(
+        &dir,
+        "gradle/deps.versions.toml",
+        "[libraries]\nsdk = { module = \"org.allaymc.allay:api\", version = \"0.26.0\" }\n",
+    );
+    write(
+        &dir,
+        "build.gradle.kts",
+        r#"
+dependencies {
+    compileOnly(deps.sdk)
+}
+"#,
+    );
+    write(
+        &dir,
+        "src/main/resources/plugin.json",
+        r#"{"entrance": "org.example.CatalogPlugin", "name": "CatalogPlugin"}"#,
+    );
+
+    let result = build_plugins_from_local(&dir);
+    let _ = fs::remove_dir_all(&dir);
+
+    let plugins = result.unwrap();
+    assert_eq!(plugins.len(), 1);
+    assert_eq!(plugins[0].api_version, "0.26.0");
+}
+
+#[test]
+fn test_build_plugins_from_local_with_allay_gradle() {
+    let dir = checkout("allaygradle", "https://github.com/example/gradle-plugin.git");
+    write(&dir, "gradle.properties", "allayGradleVersion=0.2.1\n");
+    write(
+        &dir,
+        "settings.gradle.kts",
+        r#"
+pluginManagement {
+    plugins {
+        id("org.allaymc.gradle.plugin") version allayGradleVersion
+    }
+}
+
+rootProject.name = "GradlePlugin"
+"#,
+    );
+    // No Allay dependency: AllayGradle adds it.
+    write(
+        &dir,
+        "build.gradle.kts",
+        r#"
+plugins {
+    id("org.allaymc.gradle.plugin")
+}
+"#,
+    );
+    write(
+        &dir,
+        "src/main/resources/plugin.json",
+        r#"{"entrance": "org.example.GradlePlugin", "name": "GradlePlugin"}"#,
+    );
+
+    let result = build_plugins_from_local(&dir);
+    let _ = fs::remove_dir_all(&dir);
+
+    let plugins = result.unwrap();
+    assert_eq!(plugins.len(), 1);
+    assert_eq!(plugins[0].name, "GradlePlugin");
+    assert_eq!(plugins[0].allay_gradle_version, "0.2.1");
+}
+
+#[test]
+fn test_build_plugins_from_local_with_convention_plugin() {
+    let dir = checkout("conventions", "https://github.com/example/conventions.git");
+    write(&dir, "gradle.properties", "allayVersion=0.27.0\n");
+    write(&dir, "settings.gradle.kts", "include(\"core\")\n");
+    write(&dir, "buildSrc/build.gradle.kts", "plugins {\n    `kotlin-dsl`\n}\n");
+    write(
+        &dir,
+        "buildSrc/src/main/kotlin/allay-conventions.gradle.kts",
+        r#"
+plugins {
+    java
+}
+
+val allayVersion: String by project
+
+dependencies {
+    compileOnly("org.allaymc.allay:api:$allayVersion")
+}
+"#,
+    );
+    // The module never mentions Allay itself.
+    write(&dir, "core/build.gradle.kts", "plugins {\n    id(\"allay-conventions\")\n}\n");
+    write(
+        &dir,
+        "core/src/main/resources/plugin.json",
+        r#"{"entrance": "org.example.Core", "name": "ConventionCore"}"#,
+    );
+
+    let result = build_plugins_from_local(&dir);
+    let _ = fs::remove_dir_all(&dir);
+
+    let plugins = result.unwrap();
+    assert_eq!(plugins.len(), 1);
+    assert_eq!(plugins[0].name, "ConventionCore");
+    assert_eq!(plugins[0].api_version, "0.27.0");
+}
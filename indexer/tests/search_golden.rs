@@ -0,0 +1,61 @@
+use allayindexer::plugin::load_plugins;
+use allayindexer::search::{build_documents, build_orama_index};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Write what Orama itself builds from `docs_json` to `output`, by running
+/// `orama_reference.mjs` with bun from the repository root.
+fn write_reference(root: &Path, docs_json: &str, output: &Path) {
+    let mut child = Command::new("bun")
+        .arg("run")
+        .arg(root.join("tests/fixtures/search/orama_reference.mjs"))
+        .arg(output)
+        .current_dir(root.join(".."))
+        .stdin(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn bun");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(docs_json.as_bytes())
+        .unwrap();
+    assert!(child.wait().unwrap().success(), "orama_reference.mjs failed");
+}
+
+/// The native writer must produce exactly what Orama does, since the site
+/// reads the index with the patched `restore('seqproto', ...)`.
+///
+/// `UPDATE_GOLDEN=1` rewrites `orama-index.bin` with Orama first, which
+/// needs bun and `bun install` in the repository root:
+/// `UPDATE_GOLDEN=1 cargo test --test search_golden -- --ignored`.
+#[test]
+#[ignore = "needs tests/fixtures/search/orama-index.bin, see UPDATE_GOLDEN"]
+fn test_native_index_matches_orama() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fixtures = root.join("tests/fixtures/search");
+    let golden = fixtures.join("orama-index.bin");
+    let plugins = load_plugins(&fixtures.join("index"));
+    assert_eq!(plugins.len(), 3);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        let docs_json = serde_json::to_string(&build_documents(&plugins)).unwrap();
+        write_reference(root, &docs_json, &golden);
+    }
+
+    let output = env::temp_dir().join(format!("allayindexer-search-{}.bin", std::process::id()));
+    assert!(build_orama_index(&plugins, &output));
+    let native = fs::read(&output).unwrap();
+    let _ = fs::remove_file(&output);
+
+    let expected = fs::read(&golden).expect("golden file missing, see UPDATE_GOLDEN");
+    assert!(
+        native == expected,
+        "native index ({} bytes) differs from Orama's ({} bytes)",
+        native.len(),
+        expected.len()
+    );
+}
@@ -42,13 +42,55 @@ export function useApiVersions() {
 // From src/composables/ to AllayHubIndex/ requires ../../
 // Plugins are stored in subdirectories: AllayHubIndex/{owner}/{name}.json
 const pluginModules = import.meta.glob<AllayIndex.Plugin>(
-  '../../AllayHubIndex/**/*.json',
+  [
+    '../../AllayHubIndex/**/*.json',
+    '!../../AllayHubIndex/stats.json',
+    '!../../AllayHubIndex/tombstones.json',
+  ],
   {
     eager: false,
     import: 'default',
   },
 )
 
+interface PluginStats {
+  stars?: number
+  downloads?: number
+  versions?: Record<string, number>
+}
+
+// Indexes with split statistics keep stars and downloads in one file
+const statsModules = import.meta.glob<Record<string, PluginStats>>(
+  '../../AllayHubIndex/stats.json',
+  {
+    eager: false,
+    import: 'default',
+  },
+)
+
+async function loadPluginStats(id: string): Promise<PluginStats | undefined> {
+  const loader = Object.values(statsModules)[0]
+  if (!loader) return undefined
+  const stats = await loader()
+  return stats[id]
+}
+
+function applyPluginStats(
+  plugin: AllayIndex.Plugin,
+  stats: PluginStats | undefined,
+): AllayIndex.Plugin {
+  if (!stats) return plugin
+  return {
+    ...plugin,
+    stars: plugin.stars ?? stats.stars ?? 0,
+    downloads: plugin.downloads ?? stats.downloads ?? 0,
+    versions: plugin.versions.map((v) => ({
+      ...v,
+      downloads: v.downloads ?? stats.versions?.[v.version] ?? 0,
+    })),
+  }
+}
+
 function processPluginData(data: AllayIndex.Plugin): AllayIndex.Plugin {
   const result: Record<string, unknown> = { ...data }
   for (const key of Object.keys(result)) {
@@ -99,7 +141,8 @@ export function usePlugin(
         throw new Error(`Plugin not found: ${id}`)
       }
       const rawData = await loader()
-      return processPluginData(rawData)
+      const stats = await loadPluginStats(id)
+      return applyPluginStats(processPluginData(rawData), stats)
     },
     {
       watch:
@@ -62,6 +62,7 @@ declare namespace AllayIndex {
 
     api_version: string // Current API version from main branch
     server_version?: string // Server API version if used
+    allay_gradle_version?: string // AllayGradle plugin version if applied
     dependencies?: Dependency[] // Dependencies from main branch
   }
 